@@ -1,11 +1,16 @@
+use crate::instrument::{instrument, Span};
 use agnostic::trading_pair::Coin;
 use agnostic::trading_pair::TradingPair;
 use agnostic::trading_pair::TradingPairConverter;
 use chatex_sdk_rust::models;
+use crate::fees::{FeeSchedule, NetTrade};
+use crate::policy::RequestPolicy;
 
 pub struct ChatexAccountant<TConnector> {
     client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
     price_epsilon: f64,
+    fees: FeeSchedule,
+    policy: std::sync::Arc<RequestPolicy>,
 }
 
 impl<TConnector> ChatexAccountant<TConnector> 
@@ -18,8 +23,42 @@ where
         ChatexAccountant {
             client,
             price_epsilon: 0.0001,
+            fees: FeeSchedule::default(),
+            policy: std::sync::Arc::new(RequestPolicy::default()),
         }
     }
+
+    pub fn with_price_epsilon(mut self, price_epsilon: f64) -> ChatexAccountant<TConnector> {
+        self.price_epsilon = price_epsilon;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: std::sync::Arc<RequestPolicy>) -> ChatexAccountant<TConnector> {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_fees(mut self, fees: FeeSchedule) -> ChatexAccountant<TConnector> {
+        self.fees = fees;
+        self
+    }
+
+    pub fn fees(&self) -> &FeeSchedule {
+        &self.fees
+    }
+
+    pub fn calculate_volume_with_fee(
+        &self,
+        trading_pair: &TradingPair,
+        price: f64,
+        amount: f64,
+    ) -> f64 {
+        self.fees.calculate_volume_with_fee(trading_pair, price, amount)
+    }
+
+    pub fn net_trade(&self, trade: agnostic::trade::TradeResult) -> NetTrade {
+        self.fees.net_trade(trade)
+    }
 }
 
 impl<TConnector> agnostic::market::Accountant for ChatexAccountant<TConnector>
@@ -31,10 +70,12 @@ where
         coin: Coin,
     ) -> agnostic::market::Future<Result<agnostic::currency::Currency, String>> {
         let profile = self.client.profile();
+        let policy = self.policy.clone();
         let converter = crate::converter::TradingPairConverter::default();
         let coin_as_string = String::from(converter.from_agnostic_coin(coin.clone()));
+        let span = Span::new("ask").coin(&coin);
         let future = async move {
-            match profile.get_balance_summary().await {
+            match policy.read("ask", || profile.get_balance_summary()).await {
                 Ok(balance) => balance
                     .into_iter()
                     .find(|currency| currency.coin == coin_as_string)
@@ -51,10 +92,10 @@ where
                             })
                         },
                     ),
-                Err(error) => Err(format!("{}", error)),
+                Err(error) => Err(error.into()),
             }
         };
-        Box::pin(future)
+        instrument(span, future)
     }
 
     fn ask_both(
@@ -65,11 +106,13 @@ where
         Result<(agnostic::currency::Currency, agnostic::currency::Currency), String>,
     > {
         let profile = self.client.profile();
+        let policy = self.policy.clone();
         let converter = crate::converter::TradingPairConverter::default();
+        let span = Span::new("ask_both").coin(&left).coin(&right);
         let future = async move {
             let left_coin_as_string = String::from(converter.from_agnostic_coin(left.clone()));
             let right_coin_as_string = String::from(converter.from_agnostic_coin(right));
-            match profile.get_balance_summary().await {
+            match policy.read("ask_both", || profile.get_balance_summary()).await {
                 Ok(balance) => {
                     let currencies: Vec<agnostic::currency::Currency> = balance
                         .into_iter()
@@ -102,10 +145,10 @@ where
                         Err("Invalid currencies. Found more then 2 currencies.".to_owned())
                     }
                 }
-                Err(error) => Err(format!("{}", error)),
+                Err(error) => Err(error.into()),
             }
         };
-        Box::pin(future)
+        instrument(span, future)
     }
 
     fn calculate_volume(&self, _trading_pair: TradingPair, price: f64, amount: f64) -> f64 {
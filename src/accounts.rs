@@ -0,0 +1,207 @@
+use crate::config::ChatexConfig;
+use crate::merchant::ChatexMerchant;
+use agnostic::currency::Currency;
+use agnostic::merchant::Merchant;
+use agnostic::order::{Order, OrderWithId};
+use agnostic::trade::Trade;
+use agnostic::trading_pair::{Coin, TradingPair};
+
+#[derive(Clone, Debug)]
+pub struct AccountBalance {
+    pub account: String,
+    pub currency: Currency,
+}
+
+#[derive(Clone, Debug)]
+pub struct AccountOrder {
+    pub account: String,
+    pub order: OrderWithId,
+}
+
+pub struct ChatexAccounts<TConnector> {
+    accounts: Vec<(String, std::sync::Arc<ChatexMerchant<TConnector>>)>,
+}
+
+impl<TConnector> Default for ChatexAccounts<TConnector> {
+    fn default() -> Self {
+        ChatexAccounts {
+            accounts: Vec::new(),
+        }
+    }
+}
+
+impl<TConnector> ChatexAccounts<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    pub fn from_configs(
+        connector: TConnector,
+        configs: Vec<(String, ChatexConfig)>,
+    ) -> Result<ChatexAccounts<TConnector>, String> {
+        let mut accounts = ChatexAccounts::default();
+        for (account, config) in configs {
+            let merchant = ChatexMerchant::builder(connector.clone(), config)
+                .build()
+                .map_err(|error| format!("Failed to build the account {}: {}", account, error))?;
+            accounts.add(&account, merchant)?;
+        }
+        Ok(accounts)
+    }
+
+    pub fn add(&mut self, account: &str, merchant: ChatexMerchant<TConnector>) -> Result<(), String> {
+        if self.get(account).is_some() {
+            return Err(format!("Account {} is already registered", account));
+        }
+        let merchant = merchant.with_account(account);
+        self.accounts.push((account.to_owned(), std::sync::Arc::new(merchant)));
+        Ok(())
+    }
+
+    pub fn get(&self, account: &str) -> Option<std::sync::Arc<ChatexMerchant<TConnector>>> {
+        self.accounts
+            .iter()
+            .find(|(name, _)| name == account)
+            .map(|(_, merchant)| merchant.clone())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.accounts.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn merchants(&self) -> Vec<std::sync::Arc<ChatexMerchant<TConnector>>> {
+        self.accounts.iter().map(|(_, merchant)| merchant.clone()).collect()
+    }
+
+    pub fn balances(&self, coin: Coin) -> agnostic::market::Future<Result<Vec<AccountBalance>, String>> {
+        let accounts = self.accounts.clone();
+        let future = async move {
+            let mut balances = Vec::with_capacity(accounts.len());
+            for (account, merchant) in accounts {
+                let currency = merchant
+                    .accountant()
+                    .ask(coin.clone())
+                    .await
+                    .map_err(|error| format!("{}: {}", account, error))?;
+                balances.push(AccountBalance { account, currency });
+            }
+            Ok(balances)
+        };
+        Box::pin(future)
+    }
+
+    pub fn total_balance(&self, coin: Coin) -> agnostic::market::Future<Result<Currency, String>> {
+        let balances = self.balances(coin.clone());
+        let future = async move {
+            let total = balances.await?.into_iter().fold(
+                Currency {
+                    coin,
+                    amount: 0.0,
+                    held: 0.0,
+                },
+                |mut total, balance| {
+                    total.amount += balance.currency.amount;
+                    total.held += balance.currency.held;
+                    total
+                });
+            Ok(total)
+        };
+        Box::pin(future)
+    }
+
+    pub fn open_orders(
+        &self,
+        trading_pair: TradingPair,
+    ) -> agnostic::market::Future<Result<Vec<AccountOrder>, String>> {
+        let accounts = self.accounts.clone();
+        let future = async move {
+            let mut orders = Vec::new();
+            for (account, merchant) in accounts {
+                let account_orders = merchant
+                    .sniffer()
+                    .get_my_orders(trading_pair.clone())
+                    .await
+                    .map_err(|error| format!("{}: {}", account, error))?;
+                orders.extend(account_orders.into_iter().map(|order| AccountOrder {
+                    account: account.clone(),
+                    order,
+                }));
+            }
+            Ok(orders)
+        };
+        Box::pin(future)
+    }
+
+    pub fn create_order(
+        &self,
+        account: &str,
+        order: Order,
+    ) -> agnostic::market::Future<Result<Trade, String>> {
+        match self.get(account) {
+            Some(merchant) => merchant.trader().create_order(order),
+            None => unknown_account(account),
+        }
+    }
+
+    pub fn delete_order(&self, account: &str, id: &str) -> agnostic::market::Future<Result<(), String>> {
+        match self.get(account) {
+            Some(merchant) => merchant.trader().delete_order(id),
+            None => unknown_account(account),
+        }
+    }
+}
+
+fn unknown_account<T: Send + 'static>(account: &str) -> agnostic::market::Future<Result<T, String>> {
+    let error = format!("Unknown account: {}", account);
+    Box::pin(async move { Err(error) })
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChatexAccounts;
+    use crate::fake_server::FakeChatex;
+    use crate::merchant::ChatexMerchant;
+    use agnostic::merchant::Merchant;
+    use agnostic::trading_pair::{Coin, Coins, Side, Target, TradingPair};
+    use chatex_sdk_rust::coin;
+
+    #[test]
+    fn route_and_aggregate() {
+        let first = FakeChatex::start("FIRST");
+        first.set_balance(coin::Coin::TON, 10.0);
+        let second = FakeChatex::start("SECOND");
+        second.set_balance(coin::Coin::TON, 5.0);
+        let mut accounts = ChatexAccounts::default();
+        accounts.add("first", ChatexMerchant::new(first.client())).expect("Failed to add the account");
+        accounts.add("second", ChatexMerchant::new(second.client())).expect("Failed to add the account");
+        assert!(
+            accounts.add("first", ChatexMerchant::new(first.client())).is_err(),
+            "Duplicate accounts must be rejected");
+        let ids: Vec<&str> = accounts.merchants().iter().map(|merchant| merchant.account_id()).collect();
+        assert_eq!(ids, vec!["Chatex:first", "Chatex:second"], "Every account needs its own id");
+        let total = tokio_test::block_on(accounts.total_balance(Coin::TON));
+        assert!(total.is_ok(), format!("Failed to get balances: {:#?}", total.err()));
+        assert_eq!(total.unwrap().amount, 15.0, "Invalid total balance");
+        let trading_pair = TradingPair {
+            coins: Coins::TonUsdt,
+            side: Side::Sell,
+            target: Target::Limit,
+        };
+        let order = agnostic::order::Order {
+            trading_pair: trading_pair.clone(),
+            price: 2.0,
+            amount: 4.0,
+        };
+        let trade = tokio_test::block_on(accounts.create_order("second", order.clone()));
+        assert!(trade.is_ok(), format!("Failed to create the order: {:#?}", trade.err()));
+        assert!(first.orders().is_empty(), "The order was routed to the wrong account");
+        assert_eq!(second.orders().len(), 1, "The order was not routed to the account");
+        let orders = tokio_test::block_on(accounts.open_orders(trading_pair));
+        assert!(orders.is_ok(), format!("Failed to get orders: {:#?}", orders.err()));
+        let orders = orders.unwrap();
+        assert_eq!(orders.len(), 1, "Invalid amount of orders");
+        assert_eq!(orders[0].account, "second", "Invalid account of the order");
+        assert!(
+            tokio_test::block_on(accounts.create_order("third", order)).is_err(),
+            "Unknown accounts must be rejected");
+    }
+}
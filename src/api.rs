@@ -0,0 +1,64 @@
+//! Raw access token request used by the health probe. The SDK requests tokens
+//! implicitly and hides response headers, so it can neither probe the
+//! `/auth/access-token` flow on its own nor report the server `Date` header
+//! that the clock skew is measured from. Everything else goes through the SDK.
+
+use crate::secret::Secret;
+
+pub(crate) struct ChatexApi<TConnector> {
+    client: hyper::Client<TConnector>,
+    base_url: String,
+    secret: Secret,
+}
+
+impl<TConnector> ChatexApi<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    pub fn new(connector: TConnector, base_url: hyper::Uri, secret: Secret) -> ChatexApi<TConnector> {
+        ChatexApi {
+            client: hyper::Client::builder().build(connector),
+            base_url: base_url.to_string().trim_end_matches('/').to_owned(),
+            secret,
+        }
+    }
+
+    // Returns the server time of a successful access token response.
+    pub async fn authenticate(&self) -> Result<Option<std::time::SystemTime>, String> {
+        let path = "/auth/access-token";
+        let request = hyper::Request::builder()
+            .method(hyper::Method::POST)
+            .uri(format!("{}{}", self.base_url, path))
+            .header(hyper::header::AUTHORIZATION, format!("Bearer {}", self.secret.expose()))
+            .header(hyper::header::ACCEPT, "application/json")
+            .body(hyper::Body::empty())
+            .map_err(|error| format!("Invalid request: {}", error))?;
+        let response = self
+            .client
+            .request(request)
+            .await
+            .map_err(|error| format!("{}", error))?;
+        let status = response.status();
+        let server_time = response
+            .headers()
+            .get(hyper::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| httpdate::parse_http_date(date).ok());
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|error| format!("{}", error))?;
+        if !status.is_success() {
+            return Err(format!(
+                "Request to {} failed with {}: {}",
+                path,
+                status,
+                String::from_utf8_lossy(&body)));
+        }
+        let value: serde_json::Value = serde_json::from_slice(&body)
+            .map_err(|error| format!("Invalid response from {}: {}", path, error))?;
+        match value.get("access_token").and_then(|token| token.as_str()) {
+            Some(_) => Ok(server_time),
+            None => Err("Invalid access token response".to_owned()),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::fees::FeeSchedule;
+use crate::instrument::{instrument, Span};
+use crate::policy::RequestPolicy;
+use agnostic::trading_pair::{Side, Target};
+use agnostic::trading_pair::TradingPairConverter as _;
+use std::str::FromStr;
+
+const SEARCH_ITERATIONS: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    pub rate: f64,
+    pub amount: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Edge {
+    pub pair: String,
+    pub from: String,
+    pub to: String,
+    pub fee: f64,
+    pub levels: Vec<Level>,
+}
+
+impl Edge {
+    // The taker pays `rate` of the quote coin per base coin, so the cheapest levels come first.
+    pub fn new(pair: &str, from: &str, to: &str, fee: f64, mut levels: Vec<Level>) -> Edge {
+        levels.sort_by(|left, right| left.rate.partial_cmp(&right.rate).unwrap_or(std::cmp::Ordering::Equal));
+        Edge {
+            pair: pair.to_owned(),
+            from: from.to_owned(),
+            to: to.to_owned(),
+            fee,
+            levels,
+        }
+    }
+
+    pub fn best_rate(&self) -> Option<f64> {
+        let level = self.levels.first()?;
+        Some((1.0 - self.fee) / level.rate)
+    }
+
+    pub fn capacity(&self) -> f64 {
+        self.levels.iter().map(|level| level.rate * level.amount).sum()
+    }
+
+    pub fn marginal_rate(&self, amount: f64) -> Option<f64> {
+        let mut spent = 0.0;
+        let level = self.levels.iter().find(|level| {
+            spent += level.rate * level.amount;
+            spent >= amount
+        })?;
+        Some((1.0 - self.fee) / level.rate)
+    }
+
+    pub fn convert(&self, amount: f64) -> Option<f64> {
+        let mut remaining = amount;
+        let mut received = 0.0;
+        for level in self.levels.iter() {
+            if remaining <= 0.0 {
+                break;
+            }
+            let spent = remaining.min(level.rate * level.amount);
+            received += spent / level.rate;
+            remaining -= spent;
+        }
+        if remaining > amount * 1e-12 {
+            None
+        } else {
+            Some(received * (1.0 - self.fee))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Cycle {
+    pub coins: Vec<String>,
+    pub pairs: Vec<String>,
+    pub best_return: f64,
+    pub executable_amount: f64,
+    pub expected_return: f64,
+    pub expected_profit: f64,
+}
+
+fn simulate(edges: &[&Edge], amount: f64) -> Option<f64> {
+    edges.iter().try_fold(amount, |amount, edge| edge.convert(amount))
+}
+
+fn marginal_return(edges: &[&Edge], amount: f64) -> Option<f64> {
+    let mut amount = amount;
+    let mut rate = 1.0;
+    for edge in edges {
+        rate *= edge.marginal_rate(amount)?;
+        amount = edge.convert(amount)?;
+    }
+    Some(rate - 1.0)
+}
+
+// Levels are walked best first, so the marginal return only falls with the amount
+// and the last unit worth trading is where it reaches the minimal return.
+fn executable_amount(edges: &[&Edge], min_return: f64) -> f64 {
+    let is_profitable = |amount: f64| {
+        marginal_return(edges, amount).map_or(false, |marginal| marginal >= min_return)
+    };
+    let capacity = edges[0].capacity();
+    if is_profitable(capacity) {
+        return capacity;
+    }
+    let (mut low, mut high) = (0.0, capacity);
+    for _ in 0..SEARCH_ITERATIONS {
+        let middle = (low + high) / 2.0;
+        if is_profitable(middle) {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
+pub fn find_cycles(edges: &[Edge], min_return: f64) -> Vec<Cycle> {
+    let mut cycles = Vec::new();
+    for first in edges.iter() {
+        for second in edges.iter().filter(|edge| edge.from == first.to && edge.to != first.from) {
+            for third in edges.iter().filter(|edge| edge.from == second.to && edge.to == first.from) {
+                if first.from > second.from || first.from > third.from {
+                    continue;
+                }
+                let path = [first, second, third];
+                let best_return = match path.iter().try_fold(1.0, |rate, edge| edge.best_rate().map(|best| rate * best)) {
+                    Some(rate) => rate - 1.0,
+                    None => continue,
+                };
+                if best_return <= min_return {
+                    continue;
+                }
+                let amount = executable_amount(&path, min_return);
+                let received = match simulate(&path, amount) {
+                    Some(received) if amount > 0.0 => received,
+                    _ => continue,
+                };
+                cycles.push(Cycle {
+                    coins: vec![first.from.clone(), second.from.clone(), third.from.clone(), first.from.clone()],
+                    pairs: path.iter().map(|edge| edge.pair.clone()).collect(),
+                    best_return,
+                    executable_amount: amount,
+                    expected_return: received / amount - 1.0,
+                    expected_profit: received - amount,
+                });
+            }
+        }
+    }
+    cycles.sort_by(|left, right| {
+        right.best_return.partial_cmp(&left.best_return).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    cycles
+}
+
+pub struct ArbitrageScanner<TConnector> {
+    client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
+    policy: std::sync::Arc<RequestPolicy>,
+    fees: FeeSchedule,
+    book_depth: u32,
+    min_return: f64,
+}
+
+impl<TConnector> ArbitrageScanner<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    pub fn new(client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>) -> Self {
+        ArbitrageScanner {
+            client,
+            policy: std::sync::Arc::new(RequestPolicy::default()),
+            fees: FeeSchedule::default(),
+            book_depth: 30,
+            min_return: 0.0,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: std::sync::Arc<RequestPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn with_book_depth(mut self, book_depth: u32) -> Self {
+        self.book_depth = book_depth;
+        self
+    }
+
+    pub fn with_min_return(mut self, min_return: f64) -> Self {
+        self.min_return = min_return;
+        self
+    }
+
+    pub fn scan(&self) -> agnostic::market::Future<Result<Vec<Cycle>, String>> {
+        let exchange = self.client.exchange();
+        let policy = self.policy.clone();
+        let fees = self.fees.clone();
+        let book_depth = self.book_depth;
+        let min_return = self.min_return;
+        let future = async move {
+            let converter = crate::converter::TradingPairConverter::default();
+            let mut edges = Vec::new();
+            for pair in crate::converter::supported_pairs() {
+                let name = String::from(pair.clone());
+                let trading_pair = match converter.from_pair(pair.clone(), Target::Market) {
+                    Some(trading_pair) => trading_pair,
+                    None => {
+                        log::warn!("Skipping pair with unknown coins: {}", name);
+                        continue;
+                    }
+                };
+                let (base, quote) = crate::converter::split_coins(&trading_pair.coins);
+                let (from, to) = match trading_pair.side {
+                    Side::Buy => (quote, base),
+                    Side::Sell => (base, quote),
+                };
+                let from = String::from(converter.from_agnostic_coin(from));
+                let to = String::from(converter.from_agnostic_coin(to));
+                let orders = policy
+                    .read("order_book", || exchange.get_all_orders(pair.clone(), None, Some(book_depth)))
+                    .await?;
+                let levels = orders
+                    .iter()
+                    .filter_map(|order| Some(Level {
+                        rate: f64::from_str(&order.rate).ok()?,
+                        amount: f64::from_str(&order.amount).ok()?,
+                    }))
+                    .filter(|level| level.rate > 0.0 && level.amount > 0.0)
+                    .collect();
+                edges.push(Edge::new(&name, &from, &to, fees.rate(&trading_pair), levels));
+            }
+            let cycles = find_cycles(&edges, min_return);
+            for cycle in cycles.iter() {
+                log::info!(
+                    "Arbitrage {} return {:.4}% on {} {}",
+                    cycle.coins.join("->"),
+                    cycle.expected_return * 100.0,
+                    cycle.executable_amount,
+                    cycle.coins[0]);
+            }
+            Ok(cycles)
+        };
+        instrument(Span::new("arbitrage_scan"), future)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_cycles, ArbitrageScanner, Edge, Level};
+    use crate::fake_server::FakeChatex;
+    use crate::test::SECRET;
+    use chatex_sdk_rust::coin;
+
+    fn edge(pair: &str, fee: f64, levels: &[(f64, f64)]) -> Edge {
+        let levels = levels
+            .iter()
+            .map(|(rate, amount)| Level {
+                rate: *rate,
+                amount: *amount,
+            })
+            .collect();
+        let mut coins = pair.splitn(2, '/');
+        let base = coins.next().expect("Invalid pair");
+        let quote = coins.next().expect("Invalid pair");
+        Edge::new(pair, quote, base, fee, levels)
+    }
+
+    #[test]
+    fn profitable_cycle() {
+        let edges = vec![
+            edge("TON/USDT", 0.0, &[(2.0, 10.0), (2.5, 10.0)]),
+            edge("BTC/TON", 0.0, &[(10.0, 1.0)]),
+            edge("USDT/BTC", 0.0, &[(0.04, 100.0)]),
+        ];
+        let cycles = find_cycles(&edges, 0.0);
+        assert_eq!(cycles.len(), 1, "Rotations must be reported once: {:#?}", cycles);
+        let cycle = &cycles[0];
+        assert_eq!(cycle.coins, vec!["BTC", "USDT", "TON", "BTC"], "Invalid cycle");
+        assert!((cycle.best_return - 0.25).abs() < 1e-9, "Invalid return: {}", cycle.best_return);
+        assert!(cycle.executable_amount <= 0.8 + 1e-9, "The book depth must limit the amount: {}", cycle.executable_amount);
+        assert!(cycle.executable_amount > 0.79, "The whole depth is profitable: {}", cycle.executable_amount);
+        assert!((cycle.expected_profit - 0.2).abs() < 1e-6, "Invalid profit: {}", cycle.expected_profit);
+        let cycles = find_cycles(&edges, 0.3);
+        assert!(cycles.is_empty(), "The minimal return must be respected");
+        let with_fees = vec![
+            edge("TON/USDT", 0.1, &[(2.0, 10.0)]),
+            edge("BTC/TON", 0.1, &[(10.0, 1.0)]),
+            edge("USDT/BTC", 0.1, &[(0.04, 100.0)]),
+        ];
+        assert!(find_cycles(&with_fees, 0.0).is_empty(), "Fees must be accounted for");
+    }
+
+    #[test]
+    fn marginal_return_limits_the_amount() {
+        let edges = vec![
+            edge("TON/USDT", 0.0, &[(3.0, 100.0), (2.0, 10.0)]),
+            edge("BTC/TON", 0.0, &[(10.0, 100.0)]),
+            edge("USDT/BTC", 0.0, &[(0.04, 100.0)]),
+        ];
+        assert_eq!(edges[0].levels[0].rate, 2.0, "Levels must be sorted by rate");
+        let cycles = find_cycles(&edges, 0.0);
+        assert_eq!(cycles.len(), 1, "Invalid cycles: {:#?}", cycles);
+        let cycle = &cycles[0];
+        assert!(
+            (cycle.executable_amount - 0.8).abs() < 1e-6,
+            "Only the levels with a positive marginal return are worth trading: {}",
+            cycle.executable_amount);
+        assert!((cycle.expected_profit - 0.2).abs() < 1e-6, "Invalid profit: {}", cycle.expected_profit);
+    }
+
+    #[test]
+    fn scan_supported_pairs() {
+        let chatex = FakeChatex::start(SECRET);
+        chatex.add_order(coin::CoinPair::new(coin::Coin::TON, coin::Coin::USDT), 2.0, 1.0);
+        let scanner = ArbitrageScanner::new(chatex.client()).with_min_return(0.001);
+        let cycles = tokio_test::block_on(scanner.scan());
+        assert!(cycles.is_ok(), "Failed to scan: {:#?}", cycles.err());
+        assert!(cycles.unwrap().is_empty(), "A single pair has no cycles");
+    }
+}
@@ -0,0 +1,643 @@
+use crate::converter;
+use crate::snapshot::Snapshot;
+use crate::wallet::Wallet;
+use agnostic::currency::Currency;
+use agnostic::order::{Order, OrderWithId};
+use agnostic::trade::{Trade, TradeResult};
+use agnostic::trading_pair::{Coin, Side, Target, TradingPair, TradingPairConverter};
+
+const PRICE_EPSILON: f64 = 0.00005;
+
+#[derive(Clone, Copy, Debug)]
+pub struct FillModel {
+    pub queue_position: f64,
+    pub partial_fills: bool,
+    pub maker_fee: f64,
+    pub taker_fee: f64,
+}
+
+impl Default for FillModel {
+    fn default() -> FillModel {
+        FillModel {
+            queue_position: 1.0,
+            partial_fills: true,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Fill {
+    pub timestamp: u64,
+    pub order_id: String,
+    pub trading_pair: TradingPair,
+    pub price: f64,
+    pub amount: f64,
+    pub fee_coin: Coin,
+    pub fee: f64,
+    pub is_maker: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BacktestReport {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+    pub steps: usize,
+    pub orders_created: usize,
+    pub orders_cancelled: usize,
+    pub fills: Vec<Fill>,
+    pub initial_balances: Vec<Currency>,
+    pub final_balances: Vec<Currency>,
+}
+
+impl BacktestReport {
+    pub fn fees(&self, coin: &Coin) -> f64 {
+        self.fills
+            .iter()
+            .filter(|fill| fill.fee_coin == *coin)
+            .map(|fill| fill.fee)
+            .sum()
+    }
+
+    pub fn balance_change(&self, coin: &Coin) -> f64 {
+        let amount = |balances: &[Currency]| balances
+            .iter()
+            .find(|currency| currency.coin == *coin)
+            .map_or(0.0, |currency| currency.amount + currency.held);
+        amount(&self.final_balances) - amount(&self.initial_balances)
+    }
+}
+
+struct RestingOrder {
+    order: OrderWithId,
+    remaining: f64,
+    queue_ahead: f64,
+    level_volume: f64,
+}
+
+struct BacktestState {
+    snapshots: Vec<Snapshot>,
+    cursor: usize,
+    now: Option<u64>,
+    books: Vec<(String, Vec<Order>)>,
+    consumed: Vec<(String, f64, f64)>,
+    wallet: Wallet,
+    orders: Vec<RestingOrder>,
+    next_id: u64,
+    report: BacktestReport,
+}
+
+fn book_key(trading_pair: &TradingPair) -> String {
+    converter::TradingPairConverter::default().to_string(trading_pair.clone())
+}
+
+fn market_pair(trading_pair: &TradingPair, side: Side) -> TradingPair {
+    TradingPair {
+        coins: trading_pair.coins.clone(),
+        side,
+        target: Target::Market,
+    }
+}
+
+fn opposite(side: &Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    }
+}
+
+fn crosses(side: &Side, level_price: f64, price: f64) -> bool {
+    match side {
+        Side::Buy => level_price <= price + PRICE_EPSILON,
+        Side::Sell => level_price + PRICE_EPSILON >= price,
+    }
+}
+
+impl BacktestState {
+    fn book(&self, trading_pair: &TradingPair) -> &[Order] {
+        let key = book_key(trading_pair);
+        self.books
+            .iter()
+            .find(|(book, _)| *book == key)
+            .map(|(_, orders)| orders.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("backtest-{}", self.next_id)
+    }
+
+    fn level_volume(&self, order: &OrderWithId) -> f64 {
+        let same_side = market_pair(&order.trading_pair, opposite(&order.trading_pair.side));
+        self.book(&same_side)
+            .iter()
+            .filter(|level| (level.price - order.price).abs() < PRICE_EPSILON)
+            .map(|level| level.amount)
+            .sum()
+    }
+
+    fn crossed_volume(&self, order: &OrderWithId) -> f64 {
+        let counter = market_pair(&order.trading_pair, order.trading_pair.side.clone());
+        self.book(&counter)
+            .iter()
+            .filter(|level| crosses(&order.trading_pair.side, level.price, order.price))
+            .map(|level| level.amount)
+            .sum()
+    }
+
+    fn consume(&mut self, counter: &TradingPair, side: &Side, price: f64, amount: f64) {
+        let key = book_key(counter);
+        let levels = match self.books.iter_mut().find(|(book, _)| *book == key) {
+            Some((_, levels)) => levels,
+            None => return,
+        };
+        let mut remaining = amount;
+        for level in levels.iter_mut().filter(|level| crosses(side, level.price, price)) {
+            if remaining <= 0.0 {
+                break;
+            }
+            let taken = remaining.min(level.amount);
+            level.amount -= taken;
+            remaining -= taken;
+            match self.consumed
+                .iter_mut()
+                .find(|(book, consumed, _)| *book == key && (consumed - level.price).abs() < PRICE_EPSILON)
+            {
+                Some((_, _, consumed)) => *consumed += taken,
+                None => self.consumed.push((key.clone(), level.price, taken)),
+            }
+        }
+    }
+
+    fn advance(&mut self, fill_model: &FillModel) -> bool {
+        let timestamp = match self.snapshots.get(self.cursor) {
+            Some(snapshot) => snapshot.timestamp,
+            None => return false,
+        };
+        while let Some(snapshot) = self.snapshots.get(self.cursor) {
+            if snapshot.timestamp != timestamp {
+                break;
+            }
+            let key = book_key(&snapshot.trading_pair);
+            let mut orders = snapshot.orders.clone();
+            self.consumed.retain(|(book, price, _)| {
+                *book != key || orders.iter().any(|level| (level.price - price).abs() < PRICE_EPSILON)
+            });
+            for (_, price, amount) in self.consumed.iter().filter(|(book, _, _)| *book == key) {
+                for level in orders.iter_mut().filter(|level| (level.price - price).abs() < PRICE_EPSILON) {
+                    level.amount = (level.amount - amount).max(0.0);
+                }
+            }
+            match self.books.iter_mut().find(|(book, _)| *book == key) {
+                Some((_, book)) => *book = orders,
+                None => self.books.push((key, orders)),
+            }
+            self.cursor += 1;
+        }
+        self.now = Some(timestamp);
+        self.report.start.get_or_insert(timestamp);
+        self.report.end = Some(timestamp);
+        self.report.steps += 1;
+        self.match_resting_orders(fill_model);
+        true
+    }
+
+    fn match_resting_orders(&mut self, fill_model: &FillModel) {
+        let mut index = 0;
+        while index < self.orders.len() {
+            let crossed_volume = self.crossed_volume(&self.orders[index].order);
+            let level_volume = self.level_volume(&self.orders[index].order);
+            let resting = &mut self.orders[index];
+            let is_crossed = crossed_volume > 0.0;
+            let available = if is_crossed {
+                crossed_volume
+            } else {
+                resting.queue_ahead -= (resting.level_volume - level_volume).max(0.0);
+                resting.level_volume = level_volume;
+                (-resting.queue_ahead).max(0.0)
+            };
+            let amount = match (available > 0.0, fill_model.partial_fills) {
+                (false, _) => 0.0,
+                (true, true) => resting.remaining.min(available),
+                (true, false) if available + PRICE_EPSILON >= resting.remaining => resting.remaining,
+                (true, false) => 0.0,
+            };
+            if amount <= 0.0 {
+                index += 1;
+                continue;
+            }
+            resting.queue_ahead = 0.0;
+            resting.remaining -= amount;
+            let order = resting.order.clone();
+            let is_done = resting.remaining <= PRICE_EPSILON;
+            if is_crossed {
+                let counter = market_pair(&order.trading_pair, order.trading_pair.side.clone());
+                self.consume(&counter, &order.trading_pair.side, order.price, amount);
+            }
+            let (coin, held) = Wallet::required(&order.trading_pair, order.price, amount);
+            self.wallet.release(&coin, held);
+            self.record_fill(&order, order.price, amount, fill_model.maker_fee, true);
+            if is_done {
+                self.orders.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    fn record_fill(
+        &mut self,
+        order: &OrderWithId,
+        price: f64,
+        amount: f64,
+        fee_rate: f64,
+        is_maker: bool,
+    ) {
+        let (fee_coin, fee) = self.wallet.settle(&order.trading_pair, price, amount, fee_rate);
+        self.report.fills.push(Fill {
+            timestamp: self.now.unwrap_or_default(),
+            order_id: order.id.clone(),
+            trading_pair: order.trading_pair.clone(),
+            price,
+            amount,
+            fee_coin,
+            fee,
+            is_maker,
+        });
+    }
+
+    fn execute_market_order(&mut self, order: Order, fill_model: &FillModel) -> Result<Trade, String> {
+        let mut remaining = order.amount;
+        let mut filled = 0.0;
+        let mut volume = 0.0;
+        for level in self.book(&order.trading_pair) {
+            if remaining <= 0.0 || !crosses(&order.trading_pair.side, level.price, order.price) {
+                break;
+            }
+            let amount = remaining.min(level.amount);
+            filled += amount;
+            volume += amount * level.price;
+            remaining -= amount;
+        }
+        if filled <= 0.0 {
+            return Err(format!("Failed to find the order: {:#?}", order));
+        }
+        if !fill_model.partial_fills && remaining > PRICE_EPSILON {
+            return Err(format!("Insufficient liquidity for the order: {:#?}", order));
+        }
+        let price = volume / filled;
+        let (coin, required) = Wallet::required(&order.trading_pair, price, filled);
+        self.wallet.ensure(&coin, required)?;
+        self.consume(&order.trading_pair, &order.trading_pair.side, order.price, filled);
+        let trade = OrderWithId {
+            id: self.next_id(),
+            trading_pair: order.trading_pair,
+            price,
+            amount: filled,
+        };
+        self.record_fill(&trade, price, filled, fill_model.taker_fee, false);
+        Ok(Trade::Market(TradeResult {
+            id: trade.id,
+            trading_pair: trade.trading_pair,
+            amount: filled,
+            price,
+        }))
+    }
+
+    fn place_limit_order(&mut self, order: Order, fill_model: &FillModel) -> Result<Trade, String> {
+        let (coin, held) = Wallet::required(&order.trading_pair, order.price, order.amount);
+        self.wallet.hold(&coin, held)?;
+        let order = OrderWithId {
+            id: self.next_id(),
+            trading_pair: order.trading_pair,
+            price: order.price,
+            amount: order.amount,
+        };
+        let level_volume = self.level_volume(&order);
+        self.orders.push(RestingOrder {
+            order: order.clone(),
+            remaining: order.amount,
+            queue_ahead: level_volume * fill_model.queue_position,
+            level_volume,
+        });
+        self.report.orders_created += 1;
+        Ok(Trade::Limit(order))
+    }
+
+    fn delete_order(&mut self, id: &str) -> Result<(), String> {
+        let index = self
+            .orders
+            .iter()
+            .position(|resting| resting.order.id == id)
+            .ok_or_else(|| format!("Backtest order not found: {}", id))?;
+        let resting = self.orders.remove(index);
+        let (coin, held) = Wallet::required(
+            &resting.order.trading_pair,
+            resting.order.price,
+            resting.remaining);
+        self.wallet.release(&coin, held);
+        self.report.orders_cancelled += 1;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct BacktestMerchant {
+    state: std::sync::Arc<std::sync::Mutex<BacktestState>>,
+    fill_model: FillModel,
+    price_epsilon: f64,
+}
+
+impl BacktestMerchant {
+    pub fn new(
+        mut snapshots: Vec<Snapshot>,
+        balances: Vec<Currency>,
+        fill_model: FillModel,
+    ) -> BacktestMerchant {
+        snapshots.sort_by_key(|snapshot| snapshot.timestamp);
+        let report = BacktestReport {
+            initial_balances: balances.clone(),
+            ..BacktestReport::default()
+        };
+        BacktestMerchant {
+            state: std::sync::Arc::new(std::sync::Mutex::new(BacktestState {
+                snapshots,
+                cursor: 0,
+                now: None,
+                books: Vec::new(),
+                consumed: Vec::new(),
+                wallet: Wallet::new(balances),
+                orders: Vec::new(),
+                next_id: 0,
+                report,
+            })),
+            fill_model,
+            price_epsilon: 0.0001,
+        }
+    }
+
+    pub fn from_file(
+        path: impl AsRef<std::path::Path>,
+        balances: Vec<Currency>,
+        fill_model: FillModel,
+    ) -> Result<BacktestMerchant, String> {
+        let snapshots = crate::snapshot::read_snapshots(path)?;
+        Ok(Self::new(snapshots, balances, fill_model))
+    }
+
+    pub fn now(&self) -> Option<u64> {
+        self.lock().now
+    }
+
+    pub fn advance(&self) -> bool {
+        self.lock().advance(&self.fill_model)
+    }
+
+    pub fn run<F>(&self, mut tick: F) -> BacktestReport
+    where
+        F: FnMut(&BacktestMerchant),
+    {
+        while self.advance() {
+            tick(self);
+        }
+        self.report()
+    }
+
+    pub fn report(&self) -> BacktestReport {
+        let state = self.lock();
+        BacktestReport {
+            final_balances: state.wallet.balances(),
+            ..state.report.clone()
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BacktestState> {
+        self.state.lock().expect("Backtest state is poisoned")
+    }
+}
+
+impl agnostic::merchant::Merchant for BacktestMerchant {
+    fn id(&self) -> &'static str {
+        "Backtest"
+    }
+
+    fn accountant(&self) -> std::sync::Arc<dyn agnostic::market::Accountant> {
+        std::sync::Arc::new(self.clone())
+    }
+
+    fn trader(&self) -> std::sync::Arc<dyn agnostic::market::Trader> {
+        std::sync::Arc::new(self.clone())
+    }
+
+    fn sniffer(&self) -> std::sync::Arc<dyn agnostic::market::Sniffer> {
+        std::sync::Arc::new(self.clone())
+    }
+}
+
+impl agnostic::market::Sniffer for BacktestMerchant {
+    fn all_the_best_orders(
+        &self,
+        trading_pair: TradingPair,
+        count: u32,
+    ) -> agnostic::market::Future<Result<Vec<Order>, String>> {
+        let orders: Vec<Order> = self
+            .lock()
+            .book(&trading_pair)
+            .iter()
+            .take(count as usize)
+            .map(|order| Order {
+                trading_pair: trading_pair.clone(),
+                price: order.price,
+                amount: order.amount,
+            })
+            .collect();
+        Box::pin(async move { Ok(orders) })
+    }
+
+    fn get_my_orders(
+        &self,
+        trading_pair: TradingPair,
+    ) -> agnostic::market::Future<Result<Vec<OrderWithId>, String>> {
+        let key = book_key(&trading_pair);
+        let orders: Vec<OrderWithId> = self
+            .lock()
+            .orders
+            .iter()
+            .filter(|resting| book_key(&resting.order.trading_pair) == key)
+            .map(|resting| OrderWithId {
+                amount: resting.remaining,
+                ..resting.order.clone()
+            })
+            .collect();
+        Box::pin(async move { Ok(orders) })
+    }
+}
+
+impl agnostic::market::Trader for BacktestMerchant {
+    fn create_order(&self, order: Order) -> agnostic::market::Future<Result<Trade, String>> {
+        let result = {
+            let mut state = self.lock();
+            match order.trading_pair.target {
+                Target::Market => state.execute_market_order(order, &self.fill_model),
+                Target::Limit => state.place_limit_order(order, &self.fill_model),
+            }
+        };
+        Box::pin(async move { result })
+    }
+
+    fn delete_order(&self, id: &str) -> agnostic::market::Future<Result<(), String>> {
+        let result = self.lock().delete_order(id);
+        Box::pin(async move { result })
+    }
+}
+
+impl agnostic::market::Accountant for BacktestMerchant {
+    fn ask(&self, coin: Coin) -> agnostic::market::Future<Result<Currency, String>> {
+        let balance = self.lock().wallet.balance(&coin);
+        Box::pin(async move { Ok(balance) })
+    }
+
+    fn ask_both(
+        &self,
+        left: Coin,
+        right: Coin,
+    ) -> agnostic::market::Future<Result<(Currency, Currency), String>> {
+        let state = self.lock();
+        let balances = (state.wallet.balance(&left), state.wallet.balance(&right));
+        Box::pin(async move { Ok(balances) })
+    }
+
+    fn calculate_volume(&self, _trading_pair: TradingPair, price: f64, amount: f64) -> f64 {
+        price * amount
+    }
+
+    fn nearest_price(&self, _trading_pair: TradingPair, price: f64) -> f64 {
+        price - self.price_epsilon
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BacktestMerchant, FillModel};
+    use crate::snapshot::Snapshot;
+    use agnostic::currency::Currency;
+    use agnostic::market::{Accountant, Trader};
+    use agnostic::order::Order;
+    use agnostic::trading_pair::{Coin, Coins, Side, Target, TradingPair};
+
+    fn snapshot(timestamp: u64, side: Side, price: f64, amount: f64) -> Snapshot {
+        let trading_pair = TradingPair {
+            coins: Coins::TonUsdt,
+            side,
+            target: Target::Market,
+        };
+        Snapshot {
+            timestamp,
+            trading_pair: trading_pair.clone(),
+            orders: vec![Order {
+                trading_pair,
+                price,
+                amount,
+            }],
+        }
+    }
+
+    #[test]
+    fn limit_order_fills_when_book_crosses() {
+        let snapshots = vec![
+            snapshot(1, Side::Buy, 2.1, 5.0),
+            snapshot(2, Side::Buy, 1.9, 1.0),
+            snapshot(3, Side::Buy, 1.8, 5.0),
+        ];
+        let merchant = BacktestMerchant::new(
+            snapshots,
+            vec![Currency { coin: Coin::USDT, amount: 10.0, held: 0.0 }],
+            FillModel {
+                taker_fee: 0.0,
+                maker_fee: 0.01,
+                ..FillModel::default()
+            });
+        assert!(merchant.advance());
+        let order = Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                side: Side::Buy,
+                target: Target::Limit,
+            },
+            price: 2.0,
+            amount: 2.0,
+        };
+        let trade = tokio_test::block_on(merchant.create_order(order));
+        assert!(trade.is_ok(), format!("Failed to create the order: {:#?}", trade.err()));
+        let report = merchant.run(|_| ());
+        assert_eq!(report.steps, 3, "Invalid amount of steps");
+        assert_eq!(report.fills.len(), 2, "Expected two partial fills");
+        assert_eq!(report.fills[0].amount, 1.0, "Invalid first fill");
+        assert_eq!(report.fills[1].amount, 1.0, "Invalid second fill");
+        let usdt = tokio_test::block_on(merchant.ask(Coin::USDT)).expect("Failed to ask");
+        assert_eq!(usdt.amount, 6.0, "Invalid USDT balance");
+        assert_eq!(usdt.held, 0.0, "Invalid USDT held");
+        assert!((report.balance_change(&Coin::TON) - 1.98).abs() < 1e-9, "Invalid TON change");
+        assert!((report.fees(&Coin::TON) - 0.02).abs() < 1e-9, "Invalid fees");
+    }
+
+    fn limit_buy(price: f64, amount: f64) -> Order {
+        Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                side: Side::Buy,
+                target: Target::Limit,
+            },
+            price,
+            amount,
+        }
+    }
+
+    #[test]
+    fn whole_fills_require_enough_volume() {
+        let snapshots = vec![
+            snapshot(1, Side::Buy, 2.1, 5.0),
+            snapshot(2, Side::Buy, 1.9, 1.0),
+            snapshot(3, Side::Buy, 1.8, 5.0),
+        ];
+        let merchant = BacktestMerchant::new(
+            snapshots,
+            vec![Currency { coin: Coin::USDT, amount: 10.0, held: 0.0 }],
+            FillModel {
+                partial_fills: false,
+                ..FillModel::default()
+            });
+        assert!(merchant.advance());
+        let trade = tokio_test::block_on(merchant.create_order(limit_buy(2.0, 2.0)));
+        assert!(trade.is_ok(), "Failed to create the order: {:#?}", trade.err());
+        let report = merchant.run(|_| ());
+        assert_eq!(report.fills.len(), 1, "The thin level must not fill the order: {:#?}", report.fills);
+        assert_eq!(report.fills[0].timestamp, 3, "Invalid fill step");
+        assert_eq!(report.fills[0].amount, 2.0, "Invalid fill");
+    }
+
+    #[test]
+    fn crossed_volume_is_consumed() {
+        let snapshots = vec![
+            snapshot(1, Side::Buy, 2.1, 5.0),
+            snapshot(2, Side::Buy, 1.9, 1.5),
+            snapshot(3, Side::Buy, 1.9, 1.5),
+        ];
+        let merchant = BacktestMerchant::new(
+            snapshots,
+            vec![Currency { coin: Coin::USDT, amount: 10.0, held: 0.0 }],
+            FillModel::default());
+        assert!(merchant.advance());
+        for _ in 0..2 {
+            let trade = tokio_test::block_on(merchant.create_order(limit_buy(2.0, 1.0)));
+            assert!(trade.is_ok(), "Failed to create the order: {:#?}", trade.err());
+        }
+        let report = merchant.run(|_| ());
+        let filled: f64 = report.fills.iter().map(|fill| fill.amount).sum();
+        assert!((filled - 1.5).abs() < 1e-9, "The level must be shared between orders: {:#?}", report.fills);
+        let usdt = tokio_test::block_on(merchant.ask(Coin::USDT)).expect("Failed to ask");
+        assert!((usdt.held - 1.0).abs() < 1e-9, "Invalid USDT held: {}", usdt.held);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::market_info::RoundingPolicy;
+use crate::policy::{RateLimiter, RequestPolicy};
+use crate::price_band::PriceBand;
+use crate::secret::{EnvSecret, FileSecret, Secret, SecretProvider, StdinSecret};
+use std::str::FromStr;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.chatex.com/v1";
+pub const DEFAULT_SECRET_ENV: &str = "CHATEX_SECRET";
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretSource {
+    Value(Secret),
+    Env(String),
+    File(std::path::PathBuf),
+    Stdin,
+}
+
+impl Default for SecretSource {
+    fn default() -> SecretSource {
+        SecretSource::Env(DEFAULT_SECRET_ENV.to_owned())
+    }
+}
+
+impl SecretProvider for SecretSource {
+    fn secret(&self) -> Result<Secret, String> {
+        match self {
+            SecretSource::Value(secret) => Ok(secret.clone()),
+            SecretSource::Env(name) => EnvSecret(name.clone()).secret(),
+            SecretSource::File(path) => FileSecret(path.clone()).secret(),
+            SecretSource::Stdin => StdinSecret.secret(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct ChatexConfig {
+    pub base_url: String,
+    pub secret: SecretSource,
+    pub timeout_ms: Option<u64>,
+    pub operation_timeouts_ms: std::collections::BTreeMap<String, u64>,
+    pub retries: u32,
+    pub retry_delay_ms: u64,
+    pub requests_per_second: Option<f64>,
+    pub price_epsilon: f64,
+    pub rate_tolerance: f64,
+    pub book_depth: u32,
+    pub price_band_percent: Option<f64>,
+    pub reject_imprecise_orders: bool,
+    pub exclude_own_orders: bool,
+    pub pairs: Option<Vec<String>>,
+    pub paper: bool,
+}
+
+impl Default for ChatexConfig {
+    fn default() -> ChatexConfig {
+        ChatexConfig {
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            secret: SecretSource::default(),
+            timeout_ms: None,
+            operation_timeouts_ms: std::collections::BTreeMap::new(),
+            retries: 0,
+            retry_delay_ms: 500,
+            requests_per_second: None,
+            price_epsilon: 0.0001,
+            rate_tolerance: 0.00005,
+            book_depth: 30,
+            price_band_percent: None,
+            reject_imprecise_orders: false,
+            exclude_own_orders: false,
+            pairs: None,
+            paper: false,
+        }
+    }
+}
+
+impl ChatexConfig {
+    pub fn from_json(json: &str) -> Result<ChatexConfig, String> {
+        serde_json::from_str(json).map_err(|error| format!("Invalid config: {}", error))
+    }
+
+    pub fn from_toml(toml: &str) -> Result<ChatexConfig, String> {
+        toml::from_str(toml).map_err(|error| format!("Invalid config: {}", error))
+    }
+
+    pub fn from_file(path: &std::path::Path) -> Result<ChatexConfig, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_json(&content),
+            Some("toml") => Self::from_toml(&content),
+            _ => Err(format!("Unknown config format: {}", path.display())),
+        }
+    }
+
+    pub fn from_env(prefix: &str) -> Result<ChatexConfig, String> {
+        ChatexConfig::default().with_env(prefix)
+    }
+
+    pub fn with_env(mut self, prefix: &str) -> Result<ChatexConfig, String> {
+        let var = |name: &str| std::env::var(format!("{}{}", prefix, name)).ok();
+        fn parse<T: FromStr>(name: &str, value: String) -> Result<T, String> {
+            T::from_str(&value).map_err(|_| format!("Invalid {}: {}", name, value))
+        }
+        if let Some(base_url) = var("BASE_URL") {
+            self.base_url = base_url;
+        }
+        if var("SECRET").is_some() {
+            self.secret = SecretSource::Env(format!("{}SECRET", prefix));
+        }
+        if let Some(path) = var("SECRET_FILE") {
+            self.secret = SecretSource::File(path.into());
+        }
+        if let Some(timeout_ms) = var("TIMEOUT_MS") {
+            self.timeout_ms = Some(parse("TIMEOUT_MS", timeout_ms)?);
+        }
+        if let Some(retries) = var("RETRIES") {
+            self.retries = parse("RETRIES", retries)?;
+        }
+        if let Some(retry_delay_ms) = var("RETRY_DELAY_MS") {
+            self.retry_delay_ms = parse("RETRY_DELAY_MS", retry_delay_ms)?;
+        }
+        if let Some(requests_per_second) = var("REQUESTS_PER_SECOND") {
+            self.requests_per_second = Some(parse("REQUESTS_PER_SECOND", requests_per_second)?);
+        }
+        if let Some(price_epsilon) = var("PRICE_EPSILON") {
+            self.price_epsilon = parse("PRICE_EPSILON", price_epsilon)?;
+        }
+        if let Some(rate_tolerance) = var("RATE_TOLERANCE") {
+            self.rate_tolerance = parse("RATE_TOLERANCE", rate_tolerance)?;
+        }
+        if let Some(book_depth) = var("BOOK_DEPTH") {
+            self.book_depth = parse("BOOK_DEPTH", book_depth)?;
+        }
+        if let Some(price_band_percent) = var("PRICE_BAND_PERCENT") {
+            self.price_band_percent = Some(parse("PRICE_BAND_PERCENT", price_band_percent)?);
+        }
+        if let Some(reject) = var("REJECT_IMPRECISE_ORDERS") {
+            self.reject_imprecise_orders = parse("REJECT_IMPRECISE_ORDERS", reject)?;
+        }
+        if let Some(exclude) = var("EXCLUDE_OWN_ORDERS") {
+            self.exclude_own_orders = parse("EXCLUDE_OWN_ORDERS", exclude)?;
+        }
+        if let Some(pairs) = var("PAIRS") {
+            self.pairs = Some(pairs
+                .split(',')
+                .map(|pair| pair.trim().to_owned())
+                .filter(|pair| !pair.is_empty())
+                .collect());
+        }
+        if let Some(paper) = var("PAPER") {
+            self.paper = parse("PAPER", paper)?;
+        }
+        Ok(self)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.requests_per_second.map_or(false, |limit| limit <= 0.0) {
+            return Err("requests_per_second must be positive".to_owned());
+        }
+        if self.price_band_percent.map_or(false, |percent| percent <= 0.0) {
+            return Err("price_band_percent must be positive".to_owned());
+        }
+        if self.price_epsilon < 0.0 || self.rate_tolerance < 0.0 {
+            return Err("Tolerances must not be negative".to_owned());
+        }
+        if self.book_depth == 0 {
+            return Err("book_depth must be positive".to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn request_policy(&self) -> RequestPolicy {
+        RequestPolicy {
+            timeout: self.timeout_ms.map(std::time::Duration::from_millis),
+            operation_timeouts: self
+                .operation_timeouts_ms
+                .iter()
+                .map(|(operation, timeout_ms)| (operation.clone(), std::time::Duration::from_millis(*timeout_ms)))
+                .collect(),
+            retries: self.retries,
+            retry_delay: std::time::Duration::from_millis(self.retry_delay_ms),
+            rate_limiter: self.requests_per_second.map(RateLimiter::new),
+            ..RequestPolicy::default()
+        }
+    }
+
+    pub fn price_band(&self) -> Option<PriceBand> {
+        self.price_band_percent.map(PriceBand::new)
+    }
+
+    pub fn rounding(&self) -> RoundingPolicy {
+        if self.reject_imprecise_orders {
+            RoundingPolicy::Reject
+        } else {
+            RoundingPolicy::Round
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChatexConfig, SecretSource};
+    use crate::secret::SecretProvider;
+
+    #[test]
+    fn load_from_toml_json_and_env() {
+        let toml = ChatexConfig::from_toml(r#"
+            base_url = "http://localhost:8080"
+            retries = 2
+            pairs = ["TON/USDT"]
+
+            [operation_timeouts_ms]
+            create_order = 3000
+
+            [secret]
+            file = "/run/secrets/chatex"
+        "#).expect("Failed to parse toml");
+        assert_eq!(toml.base_url, "http://localhost:8080");
+        assert_eq!(toml.retries, 2);
+        assert_eq!(
+            toml.request_policy().timeout_for("create_order"),
+            Some(std::time::Duration::from_millis(3000)));
+        assert_eq!(toml.secret, SecretSource::File("/run/secrets/chatex".into()));
+        assert_eq!(toml.book_depth, ChatexConfig::default().book_depth, "Missing keys must use defaults");
+        let json = ChatexConfig::from_json(r#"{"secret": {"value": "SECRET"}, "price_band_percent": 5.0}"#)
+            .expect("Failed to parse json");
+        let secret = json.secret.secret().expect("Failed to resolve the secret");
+        assert_eq!(secret.expose(), "SECRET");
+        assert!(!format!("{:?}", json).contains("SECRET"), "Secret leaked into the config debug output");
+        assert!(json.price_band().is_some(), "Price band must be configured");
+        std::env::set_var("CONFIG_TEST_TIMEOUT_MS", "1500");
+        std::env::set_var("CONFIG_TEST_PAIRS", "TON/USDT, BTC/USDT");
+        let env = json.with_env("CONFIG_TEST_").expect("Failed to read env");
+        assert_eq!(env.timeout_ms, Some(1500));
+        assert_eq!(env.pairs.map(|pairs| pairs.len()), Some(2));
+        assert_eq!(env.price_band_percent, Some(5.0), "Env must only override set variables");
+        std::env::set_var("CONFIG_TEST_RETRIES", "many");
+        assert!(ChatexConfig::from_env("CONFIG_TEST_").is_err(), "Invalid values must be rejected");
+    }
+}
@@ -45,3 +45,105 @@ impl trading_pair::TradingPairConverter for TradingPairConverter {
         }
     }
 }
+
+impl TradingPairConverter {
+    pub fn from_pair(&self, pair: coin::CoinPair, target: Target) -> Option<TradingPair> {
+        let key: String = pair.into();
+        supported_coins().into_iter().find_map(|coins| {
+            let direct_pair = pair_key(&coins);
+            let reversed_pair: String = trading_pair::TradingPairConverter::to_pair(self, TradingPair {
+                coins: coins.clone(),
+                side: Side::Sell,
+                target: Target::Market,
+            }).into();
+            let side = match target {
+                Target::Market if key == direct_pair => Side::Buy,
+                Target::Market if key == reversed_pair => Side::Sell,
+                Target::Limit if key == direct_pair => Side::Sell,
+                Target::Limit if key == reversed_pair => Side::Buy,
+                _ => return None,
+            };
+            Some(TradingPair {
+                coins,
+                side,
+                target,
+            })
+        })
+    }
+}
+
+pub fn supported_coins() -> Vec<Coins> {
+    vec![Coins::TonUsdt]
+}
+
+pub fn supported_pairs() -> Vec<coin::CoinPair> {
+    supported_coins()
+        .into_iter()
+        .flat_map(|coins| {
+            let converter = TradingPairConverter::default();
+            let direct = trading_pair::TradingPairConverter::to_pair(&converter, TradingPair {
+                coins,
+                side: Side::Buy,
+                target: Target::Market,
+            });
+            vec![direct.clone(), direct.reversed()]
+        })
+        .collect()
+}
+
+pub fn find_pair(pair: &str) -> Option<coin::CoinPair> {
+    supported_pairs()
+        .into_iter()
+        .find(|known| String::from(known.clone()).eq_ignore_ascii_case(pair))
+}
+
+pub fn split_coins(coins: &Coins) -> (Coin, Coin) {
+    match coins {
+        Coins::TonUsdt => (Coin::TON, Coin::USDT),
+    }
+}
+
+pub fn pair_key(coins: &Coins) -> String {
+    let converter = TradingPairConverter::default();
+    trading_pair::TradingPairConverter::to_string(&converter, TradingPair {
+        coins: coins.clone(),
+        side: Side::Buy,
+        target: Target::Market,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{pair_key, supported_coins, TradingPairConverter};
+    use agnostic::trading_pair::TradingPairConverter as _;
+    use agnostic::trading_pair::{Side, Target, TradingPair};
+    use chatex_sdk_rust::coin;
+
+    #[test]
+    fn from_pair_round_trip() {
+        let converter = TradingPairConverter::default();
+        for coins in supported_coins() {
+            for target in [Target::Market, Target::Limit].iter() {
+                for side in [Side::Buy, Side::Sell].iter() {
+                    let trading_pair = TradingPair {
+                        coins: coins.clone(),
+                        side: side.clone(),
+                        target: target.clone(),
+                    };
+                    let pair = converter.to_pair(trading_pair.clone());
+                    let restored = converter
+                        .from_pair(pair.clone(), target.clone())
+                        .expect("Supported pairs must be restored");
+                    assert_eq!(pair_key(&restored.coins), pair_key(&coins), "Invalid coins");
+                    assert_eq!(format!("{:?}", restored.side), format!("{:?}", side), "Invalid side");
+                    assert_eq!(format!("{:?}", restored.target), format!("{:?}", target), "Invalid target");
+                    let expected: String = pair.into();
+                    let actual: String = converter.to_pair(restored).into();
+                    assert_eq!(actual, expected, "The pair must survive the round trip");
+                }
+            }
+        }
+        let unsupported = coin::CoinPair::new(coin::Coin::BTC, coin::Coin::USDT);
+        assert!(converter.from_pair(unsupported, Target::Market).is_none(), "Unsupported pairs have no trading pair");
+    }
+}
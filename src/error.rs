@@ -0,0 +1,106 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    PriceOutOfBand {
+        rate: f64,
+        best_rate: f64,
+        max_deviation_percent: f64,
+    },
+    KillSwitchTriggered,
+    AmountBelowMinimum {
+        amount: f64,
+        min_amount: f64,
+    },
+    NotionalBelowMinimum {
+        notional: f64,
+        min_notional: f64,
+    },
+    InvalidPrecision {
+        value: f64,
+        step: f64,
+    },
+    PairDisabled {
+        pair: String,
+    },
+    Api {
+        kind: String,
+        message: String,
+    },
+    Timeout {
+        operation: String,
+        timeout_ms: u64,
+    },
+    Unreconciled {
+        operation: String,
+        message: String,
+    },
+}
+
+impl Error {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::PriceOutOfBand { .. } => "price_out_of_band",
+            Error::KillSwitchTriggered => "kill_switch_triggered",
+            Error::AmountBelowMinimum { .. } => "amount_below_minimum",
+            Error::NotionalBelowMinimum { .. } => "notional_below_minimum",
+            Error::InvalidPrecision { .. } => "invalid_precision",
+            Error::PairDisabled { .. } => "pair_disabled",
+            Error::Api { .. } => "api",
+            Error::Timeout { .. } => "timeout",
+            Error::Unreconciled { .. } => "unreconciled",
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::PriceOutOfBand { rate, best_rate, max_deviation_percent } => write!(
+                f,
+                "Order rate {} deviates from the best rate {} by more than {}%",
+                rate,
+                best_rate,
+                max_deviation_percent),
+            Error::KillSwitchTriggered => write!(
+                f,
+                "Kill switch is triggered. New orders are blocked"),
+            Error::AmountBelowMinimum { amount, min_amount } => write!(
+                f,
+                "Order amount {} is below the minimum {}",
+                amount,
+                min_amount),
+            Error::NotionalBelowMinimum { notional, min_notional } => write!(
+                f,
+                "Order notional {} is below the minimum {}",
+                notional,
+                min_notional),
+            Error::InvalidPrecision { value, step } => write!(
+                f,
+                "Value {} is not a multiple of the step {}",
+                value,
+                step),
+            Error::PairDisabled { pair } => write!(
+                f,
+                "Trading pair {} is not enabled",
+                pair),
+            Error::Api { message, .. } => write!(f, "{}", message),
+            Error::Timeout { operation, timeout_ms } => write!(
+                f,
+                "Operation {} timed out after {}ms",
+                operation,
+                timeout_ms),
+            Error::Unreconciled { operation, message } => write!(
+                f,
+                "Operation {} timed out and its outcome is unknown: {}",
+                operation,
+                message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for String {
+    fn from(error: Error) -> String {
+        error.to_string()
+    }
+}
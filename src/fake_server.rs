@@ -0,0 +1,615 @@
+use chatex_sdk_rust::coin::{Coin, CoinPair};
+use chatex_sdk_rust::models;
+use std::str::FromStr;
+
+#[derive(Clone)]
+pub struct FakeOrder {
+    pub id: u32,
+    pub pair: CoinPair,
+    pub rate: f64,
+    pub amount: f64,
+    pub is_mine: bool,
+}
+
+impl FakeOrder {
+    fn to_model(&self) -> models::Order {
+        let mut order: models::Order =
+            models::typed::Order::new(self.pair.clone(), self.rate, self.amount).into();
+        order.id = self.id;
+        order
+    }
+}
+
+struct FakeBalance {
+    coin: String,
+    amount: f64,
+    held: f64,
+}
+
+struct FakeState {
+    secret: String,
+    access_tokens: Vec<String>,
+    pairs: Vec<CoinPair>,
+    balances: Vec<FakeBalance>,
+    orders: Vec<FakeOrder>,
+    next_id: u32,
+}
+
+impl FakeState {
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn find_pair(&self, pair: &str) -> Option<CoinPair> {
+        self.pairs
+            .iter()
+            .find(|known| String::from((*known).clone()).eq_ignore_ascii_case(pair))
+            .cloned()
+    }
+
+    fn balance_mut(&mut self, coin: &str) -> &mut FakeBalance {
+        match self.balances.iter().position(|balance| balance.coin.eq_ignore_ascii_case(coin)) {
+            Some(index) => &mut self.balances[index],
+            None => {
+                self.balances.push(FakeBalance {
+                    coin: coin.to_owned(),
+                    amount: 0.0,
+                    held: 0.0,
+                });
+                self.balances.last_mut().expect("Balance was just pushed")
+            }
+        }
+    }
+}
+
+fn split_pair(pair: &CoinPair) -> (String, String) {
+    let pair = String::from(pair.clone());
+    let mut coins = pair.splitn(2, '/');
+    let base = coins.next().unwrap_or_default().to_owned();
+    let quote = coins.next().unwrap_or_default().to_owned();
+    (base, quote)
+}
+
+pub struct FakeChatex {
+    state: std::sync::Arc<std::sync::Mutex<FakeState>>,
+    address: std::net::SocketAddr,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl FakeChatex {
+    pub fn start(secret: &str) -> FakeChatex {
+        let state = std::sync::Arc::new(std::sync::Mutex::new(FakeState {
+            secret: secret.to_owned(),
+            access_tokens: Vec::new(),
+            pairs: crate::converter::supported_pairs(),
+            balances: Vec::new(),
+            orders: Vec::new(),
+            next_id: 0,
+        }));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")
+            .expect("Failed to bind the fake Chatex server");
+        listener.set_nonblocking(true).expect("Failed to configure the listener");
+        let address = listener.local_addr().expect("Failed to get the local address");
+        let (shutdown, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server_state = state.clone();
+        let thread = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to build the fake Chatex runtime");
+            runtime.block_on(async move {
+                let make_service = hyper::service::make_service_fn(move |_| {
+                    let state = server_state.clone();
+                    async move {
+                        Ok::<_, std::convert::Infallible>(hyper::service::service_fn(
+                            move |request| handle(state.clone(), request)))
+                    }
+                });
+                let server = hyper::Server::from_tcp(listener)
+                    .expect("Failed to start the fake Chatex server")
+                    .serve(make_service)
+                    .with_graceful_shutdown(async {
+                        shutdown_receiver.await.ok();
+                    });
+                if let Err(error) = server.await {
+                    log::error!("Fake Chatex server failed: {}", error);
+                }
+            });
+        });
+        FakeChatex {
+            state,
+            address,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn client(
+        &self,
+    ) -> std::sync::Arc<chatex_sdk_rust::ChatexClient<hyper::client::HttpConnector>> {
+        let secret = self.lock().secret.clone();
+        std::sync::Arc::new(chatex_sdk_rust::ChatexClient::new(
+            hyper::client::HttpConnector::new(),
+            self.base_url().parse().expect("Invalid url"),
+            secret))
+    }
+
+    pub fn set_balance(&self, coin: Coin, amount: f64) {
+        let mut state = self.lock();
+        let balance = state.balance_mut(&String::from(coin));
+        balance.amount = amount;
+    }
+
+    pub fn balance(&self, coin: Coin) -> (f64, f64) {
+        let mut state = self.lock();
+        let balance = state.balance_mut(&String::from(coin));
+        (balance.amount, balance.held)
+    }
+
+    pub fn add_order(&self, pair: CoinPair, rate: f64, amount: f64) -> u32 {
+        self.push_order(pair, rate, amount, false)
+    }
+
+    pub fn add_my_order(&self, pair: CoinPair, rate: f64, amount: f64) -> u32 {
+        {
+            let mut state = self.lock();
+            let (base, _) = split_pair(&pair);
+            let balance = state.balance_mut(&base);
+            balance.amount -= amount;
+            balance.held += amount;
+        }
+        self.push_order(pair, rate, amount, true)
+    }
+
+    fn push_order(&self, pair: CoinPair, rate: f64, amount: f64, is_mine: bool) -> u32 {
+        let mut state = self.lock();
+        let id = state.next_id();
+        state.orders.push(FakeOrder {
+            id,
+            pair,
+            rate,
+            amount,
+            is_mine,
+        });
+        id
+    }
+
+    pub fn orders(&self) -> Vec<FakeOrder> {
+        self.lock().orders.clone()
+    }
+
+    pub fn fill_order(&self, id: u32, amount: f64) -> Result<(), String> {
+        let mut state = self.lock();
+        fill(&mut state, id, amount, false).map(|_| ())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        self.state.lock().expect("Fake Chatex state is poisoned")
+    }
+}
+
+impl Drop for FakeChatex {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+type Reply = (hyper::StatusCode, String);
+
+async fn handle(
+    state: std::sync::Arc<std::sync::Mutex<FakeState>>,
+    request: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, std::convert::Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_owned();
+    let query = parse_query(request.uri().query().unwrap_or_default());
+    let authorization = request
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim_start_matches("Bearer ").to_owned());
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .map(|body| body.to_vec())
+        .unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let (status, body) = {
+        let mut state = state.lock().expect("Fake Chatex state is poisoned");
+        match (authorization, &method, segments.as_slice()) {
+            (None, _, _) => failure(hyper::StatusCode::UNAUTHORIZED, "Missing access token"),
+            (Some(secret), &hyper::Method::POST, ["auth", "access-token"]) => {
+                if secret == state.secret {
+                    issue_access_token(&mut state)
+                } else {
+                    failure(hyper::StatusCode::UNAUTHORIZED, "Invalid secret")
+                }
+            }
+            (Some(token), _, _) if !state.access_tokens.contains(&token) => {
+                failure(hyper::StatusCode::UNAUTHORIZED, "Invalid access token")
+            }
+            (_, &hyper::Method::GET, ["me", "balance", "summary"]) => balance_summary(&state),
+            (_, &hyper::Method::GET, ["exchange", "orders"]) => get_orders(&state, &query, false),
+            (_, &hyper::Method::GET, ["exchange", "my-orders"]) => get_orders(&state, &query, true),
+            (_, &hyper::Method::POST, ["exchange", "orders"]) => create_order(&mut state, &body),
+            (_, &hyper::Method::DELETE, ["exchange", "orders", id]) => delete_order(&mut state, id),
+            (_, &hyper::Method::POST, ["exchange", "orders", id, "trades"]) => {
+                create_trade(&mut state, id, &body)
+            }
+            _ => failure(hyper::StatusCode::NOT_FOUND, "Not found"),
+        }
+    };
+    let response = hyper::Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(hyper::Body::from(body))
+        .expect("Failed to build the response");
+    Ok(response)
+}
+
+fn json(body: serde_json::Result<String>) -> Reply {
+    match body {
+        Ok(body) => (hyper::StatusCode::OK, body),
+        Err(error) => failure(hyper::StatusCode::INTERNAL_SERVER_ERROR, &error.to_string()),
+    }
+}
+
+fn failure(status: hyper::StatusCode, message: &str) -> Reply {
+    (status, serde_json::json!({ "message": message }).to_string())
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let mut parts = parameter.splitn(2, '=');
+            let key = percent_decode(parts.next().unwrap_or_default());
+            let value = percent_decode(parts.next().unwrap_or_default());
+            (key, value)
+        })
+        .collect()
+}
+
+fn issue_access_token(state: &mut FakeState) -> Reply {
+    let access_token = format!("fake-access-token-{}", state.next_id());
+    let mut body = match serde_json::to_value(&models::AccessToken::default()) {
+        Ok(body) => body,
+        Err(error) => return failure(hyper::StatusCode::INTERNAL_SERVER_ERROR, &error.to_string()),
+    };
+    if let Some(fields) = body.as_object_mut() {
+        fields.insert("access_token".to_owned(), serde_json::Value::from(access_token.clone()));
+    }
+    state.access_tokens.push(access_token);
+    json(serde_json::to_string(&body))
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                let high = bytes.get(index + 1).copied().and_then(hex_digit);
+                let low = bytes.get(index + 2).copied().and_then(hex_digit);
+                match (high, low) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        index += 3;
+                    }
+                    _ => {
+                        decoded.push(b'%');
+                        index += 1;
+                    }
+                }
+            }
+            b'+' => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn query_value<'a>(query: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    query
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+fn body_value(body: &serde_json::Value, key: &str) -> Option<f64> {
+    match body.get(key)? {
+        serde_json::Value::String(value) => f64::from_str(value).ok(),
+        value => value.as_f64(),
+    }
+}
+
+fn balance_summary(state: &FakeState) -> Reply {
+    let balances: Vec<serde_json::Value> = state
+        .balances
+        .iter()
+        .map(|balance| serde_json::json!({
+            "coin": balance.coin,
+            "amount": balance.amount.to_string(),
+            "held": balance.held.to_string(),
+        }))
+        .collect();
+    json(serde_json::to_string(&balances))
+}
+
+fn get_orders(state: &FakeState, query: &[(String, String)], is_mine: bool) -> Reply {
+    let pair = match query_value(query, "pair") {
+        Some(pair) => match state.find_pair(pair) {
+            Some(pair) => Some(String::from(pair)),
+            None => return failure(hyper::StatusCode::BAD_REQUEST, "Unknown pair"),
+        },
+        None => None,
+    };
+    let offset = query_value(query, "offset")
+        .and_then(|offset| usize::from_str(offset).ok())
+        .unwrap_or_default();
+    let limit = query_value(query, "limit")
+        .and_then(|limit| usize::from_str(limit).ok())
+        .unwrap_or(usize::MAX);
+    let mut orders: Vec<&FakeOrder> = state
+        .orders
+        .iter()
+        .filter(|order| !is_mine || order.is_mine)
+        .filter(|order| pair.as_ref().map_or(true, |pair| *pair == String::from(order.pair.clone())))
+        .collect();
+    orders.sort_by(|left, right| left.rate.partial_cmp(&right.rate).unwrap_or(std::cmp::Ordering::Equal));
+    let orders: Vec<models::Order> = orders
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(FakeOrder::to_model)
+        .collect();
+    json(serde_json::to_string(&orders))
+}
+
+fn create_order(state: &mut FakeState, body: &[u8]) -> Reply {
+    let body: serde_json::Value = match serde_json::from_slice(body) {
+        Ok(body) => body,
+        Err(error) => return failure(hyper::StatusCode::BAD_REQUEST, &error.to_string()),
+    };
+    let pair = body.get("pair").and_then(|pair| pair.as_str()).and_then(|pair| state.find_pair(pair));
+    let (pair, amount, rate) = match (pair, body_value(&body, "amount"), body_value(&body, "rate")) {
+        (Some(pair), Some(amount), Some(rate)) if amount > 0.0 && rate > 0.0 => (pair, amount, rate),
+        _ => return failure(hyper::StatusCode::BAD_REQUEST, "Invalid order"),
+    };
+    let (base, _) = split_pair(&pair);
+    let balance = state.balance_mut(&base);
+    if balance.amount < amount {
+        return failure(hyper::StatusCode::UNPROCESSABLE_ENTITY, "Insufficient funds");
+    }
+    balance.amount -= amount;
+    balance.held += amount;
+    let order = FakeOrder {
+        id: state.next_id(),
+        pair,
+        rate,
+        amount,
+        is_mine: true,
+    };
+    state.orders.push(order.clone());
+    json(serde_json::to_string(&order.to_model()))
+}
+
+fn delete_order(state: &mut FakeState, id: &str) -> Reply {
+    let index = u32::from_str(id)
+        .ok()
+        .and_then(|id| state.orders.iter().position(|order| order.id == id && order.is_mine));
+    let order = match index {
+        Some(index) => state.orders.remove(index),
+        None => return failure(hyper::StatusCode::NOT_FOUND, "Order not found"),
+    };
+    let (base, _) = split_pair(&order.pair);
+    let balance = state.balance_mut(&base);
+    balance.held -= order.amount;
+    balance.amount += order.amount;
+    json(serde_json::to_string(&order.to_model()))
+}
+
+fn create_trade(state: &mut FakeState, id: &str, body: &[u8]) -> Reply {
+    let id = match u32::from_str(id) {
+        Ok(id) => id,
+        Err(_) => return failure(hyper::StatusCode::NOT_FOUND, "Order not found"),
+    };
+    let body: serde_json::Value = match serde_json::from_slice(body) {
+        Ok(body) => body,
+        Err(error) => return failure(hyper::StatusCode::BAD_REQUEST, &error.to_string()),
+    };
+    let amount = match body_value(&body, "amount") {
+        Some(amount) if amount > 0.0 => amount,
+        _ => return failure(hyper::StatusCode::BAD_REQUEST, "Invalid amount"),
+    };
+    let rate = match body_value(&body, "rate") {
+        Some(rate) if rate > 0.0 => rate,
+        _ => return failure(hyper::StatusCode::BAD_REQUEST, "Invalid rate"),
+    };
+    match state.orders.iter().find(|order| order.id == id) {
+        Some(order) if order.rate > rate => {
+            return failure(hyper::StatusCode::UNPROCESSABLE_ENTITY, "Order rate is above the trade rate");
+        }
+        _ => (),
+    }
+    match fill(state, id, amount, true) {
+        Ok(trade) => json(serde_json::to_string(&trade)),
+        Err(message) => failure(hyper::StatusCode::UNPROCESSABLE_ENTITY, &message),
+    }
+}
+
+fn fill(state: &mut FakeState, id: u32, amount: f64, by_me: bool) -> Result<models::Trade, String> {
+    let index = state
+        .orders
+        .iter()
+        .position(|order| order.id == id)
+        .ok_or_else(|| "Order not found".to_owned())?;
+    let order = state.orders[index].clone();
+    if order.is_mine == by_me {
+        return Err("Self trade is not allowed".to_owned());
+    }
+    if amount > order.amount {
+        return Err("Insufficient order amount".to_owned());
+    }
+    let (base, quote) = split_pair(&order.pair);
+    let volume = amount * order.rate;
+    if by_me {
+        let balance = state.balance_mut(&quote);
+        if balance.amount < volume {
+            return Err("Insufficient funds".to_owned());
+        }
+        balance.amount -= volume;
+        state.balance_mut(&base).amount += amount;
+    } else {
+        state.balance_mut(&base).held -= amount;
+        state.balance_mut(&quote).amount += volume;
+    }
+    if order.amount - amount <= 0.0 {
+        state.orders.remove(index);
+    } else {
+        state.orders[index].amount -= amount;
+    }
+    let mut trade: models::Trade =
+        models::typed::Order::new(order.pair.clone(), order.rate, amount).into();
+    trade.id = state.next_id();
+    trade.order.id = order.id;
+    Ok(trade)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{percent_decode, FakeChatex};
+    use crate::merchant::ChatexMerchant;
+    use crate::test::SECRET;
+    use agnostic::merchant::Merchant;
+    use agnostic::trade::Trade;
+    use agnostic::trading_pair::{Coin, Coins, Side, Target, TradingPair};
+    use chatex_sdk_rust::coin;
+
+    #[test]
+    fn limit_order_lifecycle() {
+        let chatex = FakeChatex::start(SECRET);
+        chatex.set_balance(coin::Coin::TON, 10.0);
+        let merchant = ChatexMerchant::new(chatex.client());
+        let trading_pair = TradingPair {
+            coins: Coins::TonUsdt,
+            side: Side::Sell,
+            target: Target::Limit,
+        };
+        let order = agnostic::order::Order {
+            trading_pair: trading_pair.clone(),
+            price: 2.0,
+            amount: 4.0,
+        };
+        let trade = tokio_test::block_on(merchant.trader().create_order(order));
+        let id = match trade {
+            Ok(Trade::Limit(order)) => order.id,
+            other => panic!("Failed to create the order: {:#?}", other),
+        };
+        let my_orders = tokio_test::block_on(merchant.sniffer().get_my_orders(trading_pair));
+        assert!(my_orders.is_ok(), format!("Failed to get my orders: {:#?}", my_orders.err()));
+        let my_orders = my_orders.unwrap();
+        assert_eq!(my_orders.len(), 1, "Invalid amount of orders");
+        assert_eq!(my_orders[0].id, id, "Invalid order id");
+        assert_eq!(chatex.balance(coin::Coin::TON), (6.0, 4.0));
+        chatex.fill_order(id.parse().expect("Invalid id"), 1.0).expect("Failed to fill the order");
+        let balances = tokio_test::block_on(
+            merchant.accountant().ask_both(Coin::TON, Coin::USDT));
+        assert!(balances.is_ok(), format!("Failed to get balances: {:#?}", balances.err()));
+        let (ton, usdt) = balances.unwrap();
+        assert_eq!(ton.amount, 6.0, "Invalid TON balance");
+        assert_eq!(ton.held, 3.0, "Invalid TON held");
+        assert_eq!(usdt.amount, 2.0, "Invalid USDT balance");
+        let deleted = tokio_test::block_on(merchant.trader().delete_order(&id));
+        assert!(deleted.is_ok(), format!("Failed to delete the order: {:#?}", deleted.err()));
+        assert_eq!(chatex.balance(coin::Coin::TON), (9.0, 0.0));
+    }
+
+    #[test]
+    fn market_order_against_seeded_book() {
+        let chatex = FakeChatex::start(SECRET);
+        chatex.set_balance(coin::Coin::USDT, 10.0);
+        chatex.add_order(coin::CoinPair::new(coin::Coin::TON, coin::Coin::USDT), 2.0, 4.0);
+        let merchant = ChatexMerchant::new(chatex.client());
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                side: Side::Buy,
+                target: Target::Market,
+            },
+            price: 2.0,
+            amount: 3.0,
+        };
+        let trade = tokio_test::block_on(merchant.trader().create_order(order));
+        assert!(trade.is_ok(), format!("Failed to create the trade: {:#?}", trade.err()));
+        assert_eq!(chatex.balance(coin::Coin::TON), (3.0, 0.0));
+        assert_eq!(chatex.balance(coin::Coin::USDT), (4.0, 0.0));
+        assert_eq!(chatex.orders()[0].amount, 1.0, "Invalid remaining amount");
+    }
+
+    #[test]
+    fn percent_decode_edges() {
+        assert_eq!(percent_decode("TON%2FUSDT"), "TON/USDT");
+        assert_eq!(percent_decode("TON%2F"), "TON/", "A trailing escape must be decoded");
+        assert_eq!(percent_decode("TON%2"), "TON%2", "An incomplete escape is kept");
+        assert_eq!(percent_decode("%\u{e9}t\u{e9}"), "%\u{e9}t\u{e9}", "Multibyte characters must not panic");
+        assert_eq!(percent_decode("a+b"), "a b");
+    }
+
+    #[test]
+    fn reject_invalid_tokens_and_rates() {
+        let chatex = FakeChatex::start(SECRET);
+        let id = chatex.add_order(coin::CoinPair::new(coin::Coin::TON, coin::Coin::USDT), 2.0, 4.0);
+        let client = hyper::Client::new();
+        let send = |method: hyper::Method, path: String, token: &str, body: &str| {
+            let request = hyper::Request::builder()
+                .method(method)
+                .uri(format!("{}{}", chatex.base_url(), path))
+                .header(hyper::header::AUTHORIZATION, format!("Bearer {}", token))
+                .body(hyper::Body::from(body.to_owned()))
+                .expect("Failed to build the request");
+            let response = tokio_test::block_on(client.request(request)).expect("Failed to send the request");
+            let status = response.status();
+            let body = tokio_test::block_on(hyper::body::to_bytes(response.into_body()))
+                .expect("Failed to read the body");
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default())
+        };
+        let (status, _) = send(hyper::Method::GET, "/exchange/orders".to_owned(), "FORGED", "");
+        assert_eq!(status, hyper::StatusCode::UNAUTHORIZED, "Unknown access tokens must be rejected");
+        let (status, _) = send(hyper::Method::GET, "/exchange/orders".to_owned(), SECRET, "");
+        assert_eq!(status, hyper::StatusCode::UNAUTHORIZED, "The secret is not an access token");
+        let (status, token) = send(hyper::Method::POST, "/auth/access-token".to_owned(), SECRET, "");
+        assert_eq!(status, hyper::StatusCode::OK, "The secret must be exchanged for a token");
+        let token = token["access_token"].as_str().expect("Missing access token").to_owned();
+        let (status, _) = send(hyper::Method::GET, "/exchange/orders".to_owned(), &token, "");
+        assert_eq!(status, hyper::StatusCode::OK, "Issued tokens must be accepted");
+        chatex.set_balance(coin::Coin::USDT, 10.0);
+        let path = format!("/exchange/orders/{}/trades", id);
+        let (status, _) = send(hyper::Method::POST, path.clone(), &token, r#"{"amount":"1","rate":"1.5"}"#);
+        assert_eq!(status, hyper::StatusCode::UNPROCESSABLE_ENTITY, "Trades below the order rate must be rejected");
+        let (status, _) = send(hyper::Method::POST, path, &token, r#"{"amount":"1","rate":"2"}"#);
+        assert_eq!(status, hyper::StatusCode::OK, "Trades at the order rate must be accepted");
+    }
+}
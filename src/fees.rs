@@ -0,0 +1,227 @@
+//! Chatex does not expose trading fees through its API, so the schedule is
+//! configured locally, e.g. with `FeeSchedule::from_json`, and falls back to
+//! zero fees.
+
+use crate::converter;
+use agnostic::trade::TradeResult;
+use agnostic::trading_pair::{Coin, Side, Target, TradingPair};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeeCoin {
+    Received,
+    Base,
+    Quote,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fee {
+    pub maker: f64,
+    pub taker: f64,
+    pub coin: FeeCoin,
+}
+
+impl Default for Fee {
+    fn default() -> Fee {
+        Fee {
+            maker: 0.0,
+            taker: 0.0,
+            coin: FeeCoin::Received,
+        }
+    }
+}
+
+impl Fee {
+    pub fn rate(&self, liquidity: Liquidity) -> f64 {
+        match liquidity {
+            Liquidity::Maker => self.maker,
+            Liquidity::Taker => self.taker,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NetTrade {
+    pub trade: TradeResult,
+    pub liquidity: Liquidity,
+    pub fee_coin: Coin,
+    pub fee: f64,
+    pub base_amount: f64,
+    pub quote_volume: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FeeSchedule {
+    default: Fee,
+    pairs: Vec<(String, Fee)>,
+}
+
+impl FeeSchedule {
+    pub fn new(default: Fee) -> FeeSchedule {
+        FeeSchedule {
+            default,
+            pairs: Vec::new(),
+        }
+    }
+
+    pub fn with_pair(mut self, trading_pair: &TradingPair, fee: Fee) -> FeeSchedule {
+        let key = converter::pair_key(&trading_pair.coins);
+        self.pairs.retain(|(pair, _)| *pair != key);
+        self.pairs.push((key, fee));
+        self
+    }
+
+    pub fn from_json(json: &str) -> Result<FeeSchedule, String> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|error| format!("Invalid fee schedule: {}", error))?;
+        let default = match value.get("default") {
+            Some(fee) => parse_fee(fee)?,
+            None => Fee::default(),
+        };
+        let mut pairs = Vec::new();
+        if let Some(fees) = value.get("pairs").and_then(|pairs| pairs.as_object()) {
+            for (pair, fee) in fees {
+                pairs.push((pair.to_owned(), parse_fee(fee)?));
+            }
+        }
+        Ok(FeeSchedule { default, pairs })
+    }
+
+    pub fn fee(&self, trading_pair: &TradingPair) -> Fee {
+        let key = converter::pair_key(&trading_pair.coins);
+        self.pairs
+            .iter()
+            .find(|(pair, _)| pair.eq_ignore_ascii_case(&key))
+            .map_or(self.default, |(_, fee)| *fee)
+    }
+
+    pub fn liquidity(trading_pair: &TradingPair) -> Liquidity {
+        match trading_pair.target {
+            Target::Market => Liquidity::Taker,
+            Target::Limit => Liquidity::Maker,
+        }
+    }
+
+    pub fn rate(&self, trading_pair: &TradingPair) -> f64 {
+        self.fee(trading_pair).rate(Self::liquidity(trading_pair))
+    }
+
+    pub fn calculate_volume_with_fee(
+        &self,
+        trading_pair: &TradingPair,
+        price: f64,
+        amount: f64,
+    ) -> f64 {
+        self.net(trading_pair, price, amount).2
+    }
+
+    pub fn net_trade(&self, trade: TradeResult) -> NetTrade {
+        let (fee_coin, fee, base_amount, quote_volume) =
+            self.net_with_coin(&trade.trading_pair, trade.price, trade.amount);
+        NetTrade {
+            liquidity: Self::liquidity(&trade.trading_pair),
+            trade,
+            fee_coin,
+            fee,
+            base_amount,
+            quote_volume,
+        }
+    }
+
+    fn net(&self, trading_pair: &TradingPair, price: f64, amount: f64) -> (f64, f64, f64) {
+        let (_, fee, base_amount, quote_volume) = self.net_with_coin(trading_pair, price, amount);
+        (fee, base_amount, quote_volume)
+    }
+
+    fn net_with_coin(
+        &self,
+        trading_pair: &TradingPair,
+        price: f64,
+        amount: f64,
+    ) -> (Coin, f64, f64, f64) {
+        let (base, quote) = converter::split_coins(&trading_pair.coins);
+        let fee = self.fee(trading_pair);
+        let rate = fee.rate(Self::liquidity(trading_pair));
+        let volume = price * amount;
+        let in_base = match (fee.coin, &trading_pair.side) {
+            (FeeCoin::Base, _) => true,
+            (FeeCoin::Quote, _) => false,
+            (FeeCoin::Received, Side::Buy) => true,
+            (FeeCoin::Received, Side::Sell) => false,
+        };
+        match (in_base, &trading_pair.side) {
+            (true, Side::Buy) => (base, amount * rate, amount * (1.0 - rate), volume),
+            (true, Side::Sell) => (base, amount * rate, amount * (1.0 + rate), volume),
+            (false, Side::Buy) => (quote, volume * rate, amount, volume * (1.0 + rate)),
+            (false, Side::Sell) => (quote, volume * rate, amount, volume * (1.0 - rate)),
+        }
+    }
+}
+
+fn parse_fee(value: &serde_json::Value) -> Result<Fee, String> {
+    let rate = |name: &str| value
+        .get(name)
+        .map_or(Some(0.0), |rate| rate.as_f64())
+        .ok_or_else(|| format!("Invalid {} fee", name));
+    let coin = match value.get("coin").and_then(|coin| coin.as_str()) {
+        None | Some("received") => FeeCoin::Received,
+        Some("base") => FeeCoin::Base,
+        Some("quote") => FeeCoin::Quote,
+        Some(other) => return Err(format!("Invalid fee coin: {}", other)),
+    };
+    Ok(Fee {
+        maker: rate("maker")?,
+        taker: rate("taker")?,
+        coin,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Fee, FeeCoin, FeeSchedule};
+    use agnostic::trade::TradeResult;
+    use agnostic::trading_pair::{Coin, Coins, Side, Target, TradingPair};
+
+    fn trading_pair(side: Side, target: Target) -> TradingPair {
+        TradingPair {
+            coins: Coins::TonUsdt,
+            side,
+            target,
+        }
+    }
+
+    #[test]
+    fn volume_with_fee() {
+        let fees = FeeSchedule::new(Fee {
+            maker: 0.001,
+            taker: 0.002,
+            coin: FeeCoin::Quote,
+        });
+        let buy = trading_pair(Side::Buy, Target::Market);
+        let sell = trading_pair(Side::Sell, Target::Limit);
+        assert!((fees.calculate_volume_with_fee(&buy, 2.0, 10.0) - 20.04).abs() < 1e-9);
+        assert!((fees.calculate_volume_with_fee(&sell, 2.0, 10.0) - 19.98).abs() < 1e-9);
+    }
+
+    #[test]
+    fn net_trade_from_json() {
+        let fees = FeeSchedule::from_json(r#"{"default": {"maker": 0.5, "taker": 0.01}}"#)
+            .expect("Failed to parse fees");
+        let trade = TradeResult {
+            id: "1".to_owned(),
+            trading_pair: trading_pair(Side::Buy, Target::Market),
+            price: 2.0,
+            amount: 10.0,
+        };
+        let net = fees.net_trade(trade);
+        assert!(net.fee_coin == Coin::TON, "Fee must be charged in the received coin");
+        assert!((net.fee - 0.1).abs() < 1e-9, "Invalid fee");
+        assert!((net.base_amount - 9.9).abs() < 1e-9, "Invalid net amount");
+        assert!((net.quote_volume - 20.0).abs() < 1e-9, "Invalid volume");
+    }
+}
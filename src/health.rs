@@ -0,0 +1,180 @@
+use crate::api::ChatexApi;
+use crate::policy::RequestPolicy;
+use crate::sniffer::ChatexSniffer;
+use agnostic::market::Sniffer;
+use agnostic::trading_pair::TradingPair;
+
+pub const CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Check {
+    pub latency: std::time::Duration,
+    pub error: Option<String>,
+}
+
+impl Check {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HealthReport {
+    pub auth: Check,
+    pub balances: Check,
+    pub currencies: usize,
+    pub order_book: Check,
+    pub trading_pair: Option<TradingPair>,
+    pub book_depth: usize,
+    pub clock_skew_seconds: Option<f64>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.auth.is_ok() && self.balances.is_ok() && self.order_book.is_ok()
+    }
+}
+
+async fn check<T, F>(future: F) -> (Check, Option<T>)
+where
+    F: std::future::Future<Output = Result<T, String>>,
+{
+    let started = std::time::Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, future).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("Timed out after {:?}", CHECK_TIMEOUT)),
+    };
+    let latency = started.elapsed();
+    match result {
+        Ok(value) => (Check { latency, error: None }, Some(value)),
+        Err(error) => (Check { latency, error: Some(error) }, None),
+    }
+}
+
+fn clock_skew(sent_at: std::time::SystemTime, latency: std::time::Duration, server_time: std::time::SystemTime) -> f64 {
+    let local_time = sent_at + latency / 2;
+    match server_time.duration_since(local_time) {
+        Ok(ahead) => ahead.as_secs_f64(),
+        Err(behind) => -behind.duration().as_secs_f64(),
+    }
+}
+
+pub(crate) async fn probe<TConnector>(
+    api: Option<std::sync::Arc<ChatexApi<TConnector>>>,
+    sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+    policy: std::sync::Arc<RequestPolicy>,
+    trading_pair: Option<TradingPair>,
+) -> HealthReport
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    let mut clock_skew_seconds = None;
+    let auth = match api {
+        Some(api) => {
+            let sent_at = std::time::SystemTime::now();
+            let (auth, response) = check(async {
+                policy
+                    .read("health_auth", || api.authenticate())
+                    .await
+                    .map_err(String::from)
+            }).await;
+            if let Some(Some(server_time)) = response {
+                clock_skew_seconds = Some(clock_skew(sent_at, auth.latency, server_time));
+            }
+            auth
+        }
+        // Without the raw api the SDK authenticates implicitly, so an
+        // authenticated read stands in for the access token request.
+        None => {
+            let exchange = sniffer.client().exchange();
+            check(async {
+                policy
+                    .read("health_auth", || exchange.get_my_orders(None, None, None, None))
+                    .await
+                    .map_err(String::from)
+            }).await.0
+        }
+    };
+    let profile = sniffer.client().profile();
+    let (balances, currencies) = check(async {
+        policy
+            .read("health_balances", || profile.get_balance_summary())
+            .await
+            .map_err(String::from)
+    }).await;
+    let (order_book, orders) = match trading_pair.clone() {
+        Some(trading_pair) => check(sniffer.all_the_best_orders(trading_pair, 1)).await,
+        None => (
+            Check {
+                latency: std::time::Duration::default(),
+                error: Some("No enabled trading pair".to_owned()),
+            },
+            None,
+        ),
+    };
+    let report = HealthReport {
+        auth,
+        balances,
+        currencies: currencies.map_or(0, |currencies| currencies.len()),
+        order_book,
+        trading_pair,
+        book_depth: orders.map_or(0, |orders| orders.len()),
+        clock_skew_seconds,
+    };
+    if report.is_healthy() {
+        log::debug!("Chatex health: {:?}", report);
+    } else {
+        log::warn!("Chatex is unhealthy: {:?}", report);
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::{ChatexConfig, SecretSource};
+    use crate::fake_server::FakeChatex;
+    use crate::merchant::ChatexMerchant;
+    use crate::secret::Secret;
+    use crate::test::SECRET;
+    use chatex_sdk_rust::coin;
+
+    #[test]
+    fn healthy_merchant() {
+        let chatex = FakeChatex::start(SECRET);
+        chatex.set_balance(coin::Coin::TON, 1.0);
+        chatex.add_order(coin::CoinPair::new(coin::Coin::TON, coin::Coin::USDT), 2.0, 4.0);
+        let config = ChatexConfig {
+            base_url: chatex.base_url(),
+            secret: SecretSource::Value(Secret::new(SECRET)),
+            ..ChatexConfig::default()
+        };
+        let merchant = ChatexMerchant::builder(hyper::client::HttpConnector::new(), config)
+            .build()
+            .expect("Failed to build the merchant");
+        let report = tokio_test::block_on(merchant.health());
+        assert!(report.is_healthy(), "Merchant must be healthy: {:#?}", report);
+        assert!(report.auth.is_ok(), "Auth must be checked when the api is configured");
+        assert_eq!(report.currencies, 1, "Invalid amount of currencies");
+        let skew = report.clock_skew_seconds.expect("The server sends its time");
+        assert!(skew.abs() < 5.0, "Invalid clock skew: {}", skew);
+        let config = ChatexConfig {
+            base_url: chatex.base_url(),
+            secret: SecretSource::Value(Secret::new("WRONG")),
+            ..ChatexConfig::default()
+        };
+        let merchant = ChatexMerchant::builder(hyper::client::HttpConnector::new(), config)
+            .build()
+            .expect("Failed to build the merchant");
+        let report = tokio_test::block_on(merchant.health());
+        assert!(!report.is_healthy(), "Invalid credentials must be reported");
+        assert!(!report.auth.is_ok(), "Auth must fail");
+        let report = tokio_test::block_on(ChatexMerchant::new(chatex.client()).health());
+        assert!(report.is_healthy(), "Auth must be checked through the SDK: {:#?}", report);
+        let wrong = std::sync::Arc::new(chatex_sdk_rust::ChatexClient::new(
+            hyper::client::HttpConnector::new(),
+            chatex.base_url().parse().expect("Invalid url"),
+            "WRONG".to_owned()));
+        let report = tokio_test::block_on(ChatexMerchant::new(wrong).health());
+        assert!(!report.auth.is_ok(), "Auth must fail without the api: {:#?}", report);
+    }
+}
@@ -0,0 +1,198 @@
+use agnostic::trading_pair::{Coin, TradingPair, TradingPairConverter};
+#[cfg(feature = "tracing")]
+use tracing::Instrument;
+
+#[derive(Clone, Debug, Default)]
+pub struct Span {
+    pub operation: &'static str,
+    pub pair: Option<String>,
+    pub side: Option<String>,
+    pub target: Option<String>,
+    pub coin: Option<String>,
+    pub order_id: Option<String>,
+    pub amount: Option<f64>,
+    pub price: Option<f64>,
+}
+
+impl Span {
+    pub fn new(operation: &'static str) -> Span {
+        Span {
+            operation,
+            ..Span::default()
+        }
+    }
+
+    pub fn trading_pair(mut self, trading_pair: &TradingPair) -> Span {
+        self.pair = Some(crate::converter::pair_key(&trading_pair.coins));
+        self.side = Some(format!("{:?}", trading_pair.side));
+        self.target = Some(format!("{:?}", trading_pair.target));
+        self
+    }
+
+    pub fn order(self, order: &agnostic::order::Order) -> Span {
+        let mut span = self.trading_pair(&order.trading_pair);
+        span.amount = Some(order.amount);
+        span.price = Some(order.price);
+        span
+    }
+
+    pub fn coin(mut self, coin: &Coin) -> Span {
+        let converter = crate::converter::TradingPairConverter::default();
+        let coin = String::from(converter.from_agnostic_coin(coin.clone()));
+        self.coin = Some(match self.coin {
+            Some(coins) => format!("{},{}", coins, coin),
+            None => coin,
+        });
+        self
+    }
+
+    pub fn order_id(mut self, id: &str) -> Span {
+        self.order_id = Some(id.to_owned());
+        self
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.operation)?;
+        let fields = [
+            ("pair", &self.pair),
+            ("side", &self.side),
+            ("target", &self.target),
+            ("coin", &self.coin),
+            ("order_id", &self.order_id),
+        ];
+        for (name, value) in fields.iter() {
+            if let Some(value) = value {
+                write!(f, " {}={}", name, value)?;
+            }
+        }
+        if let Some(amount) = self.amount {
+            write!(f, " amount={}", amount)?;
+        }
+        if let Some(price) = self.price {
+            write!(f, " price={}", price)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn tracing_span(span: &Span) -> tracing::Span {
+    let tracing_span = tracing::info_span!(
+        "chatex",
+        operation = span.operation,
+        pair = tracing::field::Empty,
+        side = tracing::field::Empty,
+        target = tracing::field::Empty,
+        coin = tracing::field::Empty,
+        order_id = tracing::field::Empty,
+        amount = tracing::field::Empty,
+        price = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        outcome = tracing::field::Empty);
+    let fields = [
+        ("pair", &span.pair),
+        ("side", &span.side),
+        ("target", &span.target),
+        ("coin", &span.coin),
+        ("order_id", &span.order_id),
+    ];
+    for (name, value) in fields.iter() {
+        if let Some(value) = value {
+            tracing_span.record(*name, &value.as_str());
+        }
+    }
+    if let Some(amount) = span.amount {
+        tracing_span.record("amount", &amount);
+    }
+    if let Some(price) = span.price {
+        tracing_span.record("price", &price);
+    }
+    tracing_span
+}
+
+pub fn instrument<T, F>(span: Span, future: F) -> agnostic::market::Future<Result<T, String>>
+where
+    T: Send + 'static,
+    F: std::future::Future<Output = Result<T, String>> + Send + 'static,
+{
+    log::debug!("{} started", span);
+    #[cfg(feature = "tracing")]
+    let tracing_span = tracing_span(&span);
+    let future = async move {
+        let started = std::time::Instant::now();
+        let result = future.await;
+        let latency = started.elapsed().as_millis();
+        #[cfg(feature = "tracing")]
+        {
+            let current = tracing::Span::current();
+            current.record("latency_ms", &(latency as u64));
+            match &result {
+                Ok(_) => {
+                    current.record("outcome", &"ok");
+                    tracing::debug!("{} succeeded", span.operation);
+                }
+                Err(error) => {
+                    current.record("outcome", &"error");
+                    tracing::warn!("{} failed: {}", span.operation, error);
+                }
+            }
+        }
+        match &result {
+            Ok(_) => log::debug!("{} latency_ms={} outcome=ok", span, latency),
+            Err(error) => log::warn!("{} latency_ms={} outcome=error: {}", span, latency, error),
+        }
+        result
+    };
+    #[cfg(feature = "tracing")]
+    let future = future.instrument(tracing_span);
+    Box::pin(future)
+}
+
+pub(crate) fn record_order_id(id: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("order_id", &id);
+    log::debug!("order_id={}", id);
+}
+
+pub(crate) fn debug(message: std::fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!("{}", message);
+    log::debug!("{}", message);
+}
+
+pub(crate) fn info(message: std::fmt::Arguments<'_>) {
+    #[cfg(feature = "tracing")]
+    tracing::info!("{}", message);
+    log::info!("{}", message);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{instrument, Span};
+    use agnostic::trading_pair::{Coin, Coins, Side, Target, TradingPair};
+
+    #[test]
+    fn span_fields() {
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                side: Side::Buy,
+                target: Target::Limit,
+            },
+            price: 2.0,
+            amount: 4.0,
+        };
+        let span = Span::new("create_order").order(&order).order_id("42");
+        let summary = span.to_string();
+        assert!(summary.starts_with("create_order"), "Invalid operation: {}", summary);
+        for field in ["side=Buy", "target=Limit", "order_id=42", "amount=4", "price=2"].iter() {
+            assert!(summary.contains(field), "Missing {} in {}", field, summary);
+        }
+        let coins = Span::new("ask_both").coin(&Coin::TON).coin(&Coin::USDT).to_string();
+        assert!(coins.to_uppercase().contains("COIN=TON,USDT"), "Both coins must be recorded: {}", coins);
+        let result = tokio_test::block_on(instrument(span, async { Err::<(), _>("failed".to_owned()) }));
+        assert_eq!(result, Err("failed".to_owned()), "The outcome must be passed through");
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KillSwitchState {
+    Armed,
+    Triggered,
+}
+
+pub trait CancelAllOrders: Send + Sync {
+    fn cancel_all_orders(&self) -> agnostic::market::Future<Result<Vec<String>, String>>;
+}
+
+#[derive(Default)]
+pub struct KillSwitchFlag {
+    triggered: AtomicBool,
+    listeners: std::sync::Mutex<Vec<std::sync::mpsc::Sender<KillSwitchState>>>,
+}
+
+impl KillSwitchFlag {
+    pub fn state(&self) -> KillSwitchState {
+        if self.is_triggered() {
+            KillSwitchState::Triggered
+        } else {
+            KillSwitchState::Armed
+        }
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered.load(Ordering::SeqCst)
+    }
+
+    fn set(&self, state: KillSwitchState) {
+        let triggered = state == KillSwitchState::Triggered;
+        if self.triggered.swap(triggered, Ordering::SeqCst) == triggered {
+            return;
+        }
+        log::warn!("Kill switch state changed: {:?}", state);
+        let mut listeners = self.listeners.lock().expect("Kill switch listeners are poisoned");
+        listeners.retain(|listener| listener.send(state).is_ok());
+    }
+
+    fn subscribe(&self) -> std::sync::mpsc::Receiver<KillSwitchState> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.listeners.lock().expect("Kill switch listeners are poisoned").push(sender);
+        receiver
+    }
+}
+
+#[derive(Clone)]
+pub struct KillSwitch {
+    flag: std::sync::Arc<KillSwitchFlag>,
+    trader: std::sync::Arc<dyn CancelAllOrders>,
+}
+
+impl KillSwitch {
+    pub fn new(
+        flag: std::sync::Arc<KillSwitchFlag>,
+        trader: std::sync::Arc<dyn CancelAllOrders>,
+    ) -> KillSwitch {
+        KillSwitch { flag, trader }
+    }
+
+    pub fn trigger(&self) -> agnostic::market::Future<Result<Vec<String>, String>> {
+        self.flag.set(KillSwitchState::Triggered);
+        let trader = self.trader.clone();
+        let future = async move {
+            match trader.cancel_all_orders().await {
+                Ok(ids) => {
+                    log::warn!("Kill switch cancelled orders: {:?}", ids);
+                    Ok(ids)
+                }
+                Err(error) => {
+                    log::error!("Kill switch failed to cancel orders: {}", error);
+                    Err(error)
+                }
+            }
+        };
+        Box::pin(future)
+    }
+
+    pub fn rearm(&self) {
+        self.flag.set(KillSwitchState::Armed);
+    }
+
+    pub fn state(&self) -> KillSwitchState {
+        self.flag.state()
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.flag.is_triggered()
+    }
+
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<KillSwitchState> {
+        self.flag.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::TestCase;
+    use crate::test::SERDE_ERROR;
+    use crate::merchant::ChatexMerchant;
+    use crate::error::Error;
+    use agnostic::merchant::Merchant;
+    use agnostic::trading_pair::{TradingPair, Coins, Target, Side};
+    use super::KillSwitchState;
+
+    #[test]
+    fn trigger_and_rearm() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let my_orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET);
+            let order = chatex_sdk_rust::models::typed::Order::new(
+                chatex_sdk_rust::coin::CoinPair::new(
+                    chatex_sdk_rust::coin::Coin::TON,
+                    chatex_sdk_rust::coin::Coin::USDT),
+                2.0,
+                4.0);
+            let body: chatex_sdk_rust::models::Order = order.into();
+            let body = serde_json::to_string(&vec![body]).expect(SERDE_ERROR);
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body);
+        });
+        let delete_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::DELETE);
+            let order = chatex_sdk_rust::models::typed::Order::new(
+                chatex_sdk_rust::coin::CoinPair::new(
+                    chatex_sdk_rust::coin::Coin::TON,
+                    chatex_sdk_rust::coin::Coin::USDT),
+                2.0,
+                4.0);
+            let body: chatex_sdk_rust::models::Order = order.into();
+            let body = serde_json::to_string(&body).expect(SERDE_ERROR);
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body);
+        });
+        let merchant = ChatexMerchant::new(test_case.client.clone());
+        let kill_switch = merchant.kill_switch();
+        let states = kill_switch.subscribe();
+        assert_eq!(kill_switch.state(), KillSwitchState::Armed);
+        let cancelled = tokio_test::block_on(kill_switch.trigger());
+        assert!(cancelled.is_ok(), format!("Failed to cancel orders: {:#?}", cancelled.err()));
+        assert_eq!(cancelled.unwrap().len(), 1, "Invalid amount of cancelled orders");
+        assert_eq!(kill_switch.state(), KillSwitchState::Triggered);
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                target: Target::Limit,
+                side: Side::Sell,
+            },
+            amount: 2.0,
+            price: 2.0,
+        };
+        let trade_result = tokio_test::block_on(merchant.trader().create_order(order));
+        assert_eq!(trade_result.err(), Some(Error::KillSwitchTriggered.to_string()));
+        kill_switch.rearm();
+        assert_eq!(kill_switch.state(), KillSwitchState::Armed);
+        assert_eq!(states.try_iter().collect::<Vec<_>>(), vec![
+            KillSwitchState::Triggered,
+            KillSwitchState::Armed,
+        ]);
+        auth_mock.assert_hits(2);
+        my_orders_mock.assert();
+        delete_mock.assert();
+    }
+}
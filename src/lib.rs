@@ -4,5 +4,29 @@ pub mod trader;
 pub mod merchant;
 pub mod order;
 pub mod converter;
+pub mod error;
+pub mod price_band;
+pub mod fees;
+pub mod market_info;
+pub(crate) mod api;
+pub mod kill_switch;
+pub mod paper;
+pub mod replay;
+pub mod snapshot;
+pub mod backtest;
+pub mod recorder;
+pub mod policy;
+pub mod config;
+pub mod secret;
+pub mod accounts;
+pub mod instrument;
+pub mod metrics;
+pub mod health;
+pub mod token;
+pub mod quote;
+pub mod arbitrage;
+pub(crate) mod wallet;
+#[cfg(any(test, feature = "fake-server"))]
+pub mod fake_server;
 #[cfg(test)]
 pub(crate) mod test;
@@ -0,0 +1,336 @@
+use crate::converter;
+use crate::error::Error;
+use crate::order::ChatexOrder;
+use crate::policy::RequestPolicy;
+use agnostic::trading_pair::{Side, Target, TradingPair};
+use chatex_sdk_rust::coin::CoinPair;
+
+const STEP_EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoundingPolicy {
+    Round,
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MarketRules {
+    pub min_amount: f64,
+    pub min_notional: f64,
+    pub amount_step: f64,
+    pub price_step: f64,
+}
+
+fn step_decimals(step: f64) -> usize {
+    let mut decimals = 0;
+    let mut scaled = step;
+    while decimals < 12 && (scaled.round() - scaled).abs() > STEP_EPSILON {
+        scaled *= 10.0;
+        decimals += 1;
+    }
+    decimals
+}
+
+fn clean(value: f64, step: f64) -> f64 {
+    format!("{:.*}", step_decimals(step), value).parse().unwrap_or(value)
+}
+
+fn floor_to_step(value: f64, step: f64) -> f64 {
+    if step <= 0.0 {
+        return value;
+    }
+    clean((value / step + STEP_EPSILON).floor() * step, step)
+}
+
+fn ceil_to_step(value: f64, step: f64) -> f64 {
+    if step <= 0.0 {
+        return value;
+    }
+    clean((value / step - STEP_EPSILON).ceil() * step, step)
+}
+
+fn is_on_step(value: f64, step: f64) -> bool {
+    step <= 0.0 || ((value / step).round() - value / step).abs() <= STEP_EPSILON * 1e3
+}
+
+impl MarketRules {
+    pub fn round_amount(&self, amount: f64) -> f64 {
+        floor_to_step(amount, self.amount_step)
+    }
+
+    pub fn round_price(&self, side: &Side, price: f64) -> f64 {
+        match side {
+            Side::Buy => floor_to_step(price, self.price_step),
+            Side::Sell => ceil_to_step(price, self.price_step),
+        }
+    }
+
+    pub fn min_order_amount(&self, price: f64) -> f64 {
+        let amount = if price > 0.0 {
+            self.min_amount.max(self.min_notional / price)
+        } else {
+            self.min_amount
+        };
+        ceil_to_step(amount, self.amount_step)
+    }
+
+    pub fn validate(&self, order: &ChatexOrder) -> Result<(), Error> {
+        if !is_on_step(order.amount, self.amount_step) {
+            return Err(Error::InvalidPrecision {
+                value: order.amount,
+                step: self.amount_step,
+            });
+        }
+        if !is_on_step(order.rate, self.price_step) {
+            return Err(Error::InvalidPrecision {
+                value: order.rate,
+                step: self.price_step,
+            });
+        }
+        if order.amount < self.min_amount {
+            return Err(Error::AmountBelowMinimum {
+                amount: order.amount,
+                min_amount: self.min_amount,
+            });
+        }
+        let notional = order.rate * order.amount;
+        if notional < self.min_notional {
+            return Err(Error::NotionalBelowMinimum {
+                notional,
+                min_notional: self.min_notional,
+            });
+        }
+        Ok(())
+    }
+
+    // Limit orders sell the base coin of the Chatex pair and market orders buy it.
+    pub fn normalize(
+        &self,
+        order: ChatexOrder,
+        target: &Target,
+        policy: RoundingPolicy,
+    ) -> Result<ChatexOrder, Error> {
+        let side = match target {
+            Target::Limit => Side::Sell,
+            Target::Market => Side::Buy,
+        };
+        let order = match policy {
+            RoundingPolicy::Reject => order,
+            RoundingPolicy::Round => ChatexOrder {
+                amount: self.round_amount(order.amount),
+                rate: self.round_price(&side, order.rate),
+                ..order
+            },
+        };
+        self.validate(&order)?;
+        Ok(order)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MarketInfo {
+    default: MarketRules,
+    rules: Vec<(String, MarketRules)>,
+    enabled_pairs: Option<Vec<String>>,
+    listed_pairs: std::sync::RwLock<Option<Vec<String>>>,
+}
+
+impl MarketInfo {
+    pub fn new(default: MarketRules) -> MarketInfo {
+        MarketInfo {
+            default,
+            ..MarketInfo::default()
+        }
+    }
+
+    pub fn with_rules(mut self, pair: &CoinPair, rules: MarketRules) -> MarketInfo {
+        let key = String::from(pair.clone());
+        self.rules.retain(|(pair, _)| *pair != key);
+        self.rules.push((key, rules));
+        self
+    }
+
+    pub fn with_enabled_pairs(mut self, pairs: Vec<String>) -> MarketInfo {
+        self.enabled_pairs = Some(pairs);
+        self
+    }
+
+    pub fn is_enabled(&self, trading_pair: &TradingPair) -> bool {
+        let key = converter::pair_key(&trading_pair.coins);
+        self.enabled_pairs
+            .as_ref()
+            .map_or(true, |pairs| pairs.iter().any(|pair| pair.eq_ignore_ascii_case(&key)))
+    }
+
+    pub fn is_listed(&self, pair: &CoinPair) -> bool {
+        let key = String::from(pair.clone());
+        match self.listed_pairs.read() {
+            Ok(listed) => listed
+                .as_ref()
+                .map_or(true, |pairs| pairs.iter().any(|pair| pair.eq_ignore_ascii_case(&key))),
+            Err(_) => true,
+        }
+    }
+
+    pub fn rules(&self, pair: &CoinPair) -> MarketRules {
+        let key = String::from(pair.clone());
+        self.rules
+            .iter()
+            .find(|(pair, _)| pair.eq_ignore_ascii_case(&key))
+            .map_or(self.default, |(_, rules)| *rules)
+    }
+
+    pub fn listed_pairs(&self) -> Option<Vec<String>> {
+        self.listed_pairs.read().ok().and_then(|listed| listed.clone())
+    }
+
+    pub fn set_listed_pairs(&self, pairs: Vec<String>) {
+        log::info!("Listed pairs updated: {} pairs", pairs.len());
+        if let Ok(mut listed) = self.listed_pairs.write() {
+            *listed = Some(pairs);
+        }
+    }
+}
+
+/// Checks which of the supported pairs the exchange still lists. The SDK has no
+/// market discovery endpoint, so new coins and pairs are not found here and
+/// precisions and limits come from the locally configured `MarketRules`.
+pub struct ListingProbe<TConnector> {
+    client: std::sync::Arc<chatex_sdk_rust::ExchangeClient<TConnector>>,
+    policy: std::sync::Arc<RequestPolicy>,
+    market_info: std::sync::Arc<MarketInfo>,
+}
+
+impl<TConnector> ListingProbe<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    pub fn new(
+        client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
+        market_info: std::sync::Arc<MarketInfo>,
+    ) -> ListingProbe<TConnector> {
+        ListingProbe {
+            client: std::sync::Arc::new(client.exchange()),
+            policy: std::sync::Arc::new(RequestPolicy::default()),
+            market_info,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: std::sync::Arc<RequestPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn market_info(&self) -> std::sync::Arc<MarketInfo> {
+        self.market_info.clone()
+    }
+
+    // A pair is listed when the exchange serves its order book.
+    pub fn probe(&self) -> agnostic::market::Future<Result<usize, String>> {
+        let client = self.client.clone();
+        let policy = self.policy.clone();
+        let market_info = self.market_info.clone();
+        let future = async move {
+            let mut listed = Vec::new();
+            let mut rejected = Vec::new();
+            for pair in converter::supported_pairs() {
+                let name = String::from(pair.clone());
+                match policy
+                    .read("market_info", || client.get_all_orders(pair.clone(), None, Some(1)))
+                    .await
+                {
+                    Ok(_) => listed.push(name),
+                    Err(error @ Error::Api { .. }) => {
+                        log::warn!("Pair {} is not listed: {}", name, error);
+                        rejected.push(format!("{}: {}", name, error));
+                    }
+                    Err(error) => return Err(error.into()),
+                }
+            }
+            if listed.is_empty() {
+                return Err(format!("No listed pairs found: {}", rejected.join(", ")));
+            }
+            let count = listed.len();
+            market_info.set_listed_pairs(listed);
+            Ok(count)
+        };
+        Box::pin(future)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MarketInfo, MarketRules, RoundingPolicy};
+    use crate::order::ChatexOrder;
+    use agnostic::trading_pair::Target;
+    use chatex_sdk_rust::coin;
+
+    fn pair() -> coin::CoinPair {
+        coin::CoinPair::new(coin::Coin::TON, coin::Coin::USDT)
+    }
+
+    fn order(rate: f64, amount: f64) -> ChatexOrder {
+        ChatexOrder {
+            id: None,
+            pair: pair(),
+            rate,
+            amount,
+        }
+    }
+
+    #[test]
+    fn normalize() {
+        let market_info = MarketInfo::default().with_rules(&pair(), MarketRules {
+            min_amount: 1.0,
+            min_notional: 5.0,
+            amount_step: 0.01,
+            price_step: 0.001,
+        });
+        let rules = market_info.rules(&pair());
+        assert_eq!(market_info.rules(&pair().reversed()), MarketRules::default(), "Rules are per direction");
+        let rounded = rules.normalize(order(2.00049, 3.14159), &Target::Market, RoundingPolicy::Round)
+            .expect("Order must be rounded");
+        assert_eq!(rounded.amount, 3.14, "Invalid amount");
+        assert_eq!(rounded.rate, 2.0, "Invalid rate");
+        let rounded = rules.normalize(order(2.00049, 3.14159), &Target::Limit, RoundingPolicy::Round)
+            .expect("Order must be rounded");
+        assert_eq!(rounded.rate, 2.001, "Limit rate must be rounded up");
+        assert!(rules.normalize(order(2.00049, 3.0), &Target::Limit, RoundingPolicy::Reject).is_err());
+        assert!(rules.normalize(order(2.0, 0.5), &Target::Limit, RoundingPolicy::Round).is_err());
+        assert!(rules.normalize(order(2.0, 2.0), &Target::Limit, RoundingPolicy::Round).is_err());
+        assert_eq!(rules.min_order_amount(2.0), 2.5, "Invalid minimal amount");
+    }
+
+    #[test]
+    fn probe_listed_pairs() {
+        let chatex = crate::fake_server::FakeChatex::start(crate::test::SECRET);
+        let probe = super::ListingProbe::new(chatex.client(), std::sync::Arc::new(MarketInfo::default()));
+        assert!(probe.market_info().is_listed(&pair()), "Pairs are allowed before the first probe");
+        let count = tokio_test::block_on(probe.probe());
+        assert_eq!(count, Ok(crate::converter::supported_pairs().len()), "Invalid amount of pairs");
+        let listed = probe.market_info().listed_pairs().expect("Pairs must be listed");
+        assert!(listed.contains(&String::from(pair())), "Invalid listed pairs: {:?}", listed);
+        probe.market_info().set_listed_pairs(vec![String::from(pair())]);
+        assert!(!probe.market_info().is_listed(&pair().reversed()), "Only listed pairs are allowed");
+    }
+
+    #[test]
+    fn probe_without_listed_pairs() {
+        let test_case = crate::test::TestCase::default();
+        let _auth_mock = test_case.mock_access_token();
+        let orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path("/exchange/orders");
+            then.status(400)
+                .header("Content-Type", "application/json")
+                .body(r#"{"message": "Unknown pair"}"#);
+        });
+        let probe = super::ListingProbe::new(
+            test_case.client.clone(),
+            std::sync::Arc::new(MarketInfo::default()));
+        let count = tokio_test::block_on(probe.probe());
+        assert!(count.is_err(), "A probe without listed pairs must fail");
+        assert_eq!(probe.market_info().listed_pairs(), None, "Listed pairs must not be updated");
+        orders_mock.assert_hits(crate::converter::supported_pairs().len());
+    }
+}
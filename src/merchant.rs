@@ -1,49 +1,432 @@
 use super::accountant;
 use super::sniffer;
 use super::trader;
+use super::paper;
+use super::price_band::PriceBand;
+use super::kill_switch::KillSwitch;
+use super::market_info::{MarketInfo, ListingProbe};
+use super::api::ChatexApi;
+use super::config::ChatexConfig;
+use super::fees::FeeSchedule;
+use super::secret::SecretProvider;
+use super::metrics::MetricsRecorder;
+use super::policy::RequestPolicy;
+use super::health::{self, HealthReport};
+use super::token::{TokenCache, TokenCachingConnector};
+use super::converter;
+use agnostic::trading_pair::{Side, Target, TradingPair};
+
+enum Mode<TConnector> {
+    Live {
+        accountant: std::sync::Arc<accountant::ChatexAccountant<TConnector>>,
+        trader: std::sync::Arc<trader::ChatexTrader<TConnector>>,
+    },
+    Paper(std::sync::Arc<paper::PaperExchange<TConnector>>),
+}
 
 pub struct ChatexMerchant<TConnector> {
-    accountant: std::sync::Arc<accountant::ChatexAccountant<TConnector>>,
     sniffer: std::sync::Arc<sniffer::ChatexSniffer<TConnector>>,
-    trader: std::sync::Arc<trader::ChatexTrader<TConnector>>,
+    mode: Mode<TConnector>,
+    market_info: std::sync::Arc<MarketInfo>,
+    client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
+    policy: std::sync::Arc<RequestPolicy>,
+    api: Option<std::sync::Arc<ChatexApi<TConnector>>>,
+    account: Option<String>,
+    id: std::sync::Arc<str>,
 }
 
+const ID: &str = "Chatex";
+
 impl<TConnector> ChatexMerchant<TConnector>
 where
     TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
 {
     pub fn new(client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>) -> Self {
+        let trader = trader::ChatexTrader::new(std::sync::Arc::new(client.exchange()));
+        Self::with_trader(client, trader)
+    }
+
+    pub fn with_price_band(
+        client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
+        price_band: PriceBand,
+    ) -> Self {
+        let trader = trader::ChatexTrader::new(std::sync::Arc::new(client.exchange()))
+            .with_price_band(price_band);
+        Self::with_trader(client, trader)
+    }
+
+    pub fn paper(
+        client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
+        balances: Vec<agnostic::currency::Currency>,
+    ) -> Self {
+        let sniffer = std::sync::Arc::new(
+            sniffer::ChatexSniffer::new(client.clone()));
+        let exchange = std::sync::Arc::new(
+            paper::PaperExchange::new(sniffer.clone(), balances));
+        ChatexMerchant {
+            sniffer,
+            mode: Mode::Paper(exchange),
+            market_info: std::sync::Arc::new(MarketInfo::default()),
+            client,
+            policy: std::sync::Arc::new(RequestPolicy::default()),
+            api: None,
+            account: None,
+            id: std::sync::Arc::from(ID),
+        }
+    }
+
+    pub fn account_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn with_account(mut self, account: &str) -> Self {
+        self.id = std::sync::Arc::from(format!("{}:{}", ID, account));
+        self.account = Some(account.to_owned());
+        self
+    }
+
+    pub fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+
+    pub fn builder(connector: TConnector, config: ChatexConfig) -> ChatexMerchantBuilder<TConnector> {
+        ChatexMerchantBuilder::new(connector, config)
+    }
+
+    fn with_trader(
+        client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
+        trader: trader::ChatexTrader<TConnector>,
+    ) -> Self {
         let accountant = std::sync::Arc::new(
             accountant::ChatexAccountant::new(client.clone()));
         let sniffer = std::sync::Arc::new(
             sniffer::ChatexSniffer::new(client.clone()));
-        let trader = std::sync::Arc::new(
-            trader::ChatexTrader::new(std::sync::Arc::new(client.exchange())));
         ChatexMerchant { 
-            accountant,
             sniffer,
-            trader,
+            market_info: trader.market_info(),
+            mode: Mode::Live {
+                accountant,
+                trader: std::sync::Arc::new(trader),
+            },
+            client,
+            policy: std::sync::Arc::new(RequestPolicy::default()),
+            api: None,
+            account: None,
+            id: std::sync::Arc::from(ID),
+        }
+    }
+
+    pub fn is_paper(&self) -> bool {
+        match self.mode {
+            Mode::Live { .. } => false,
+            Mode::Paper(_) => true,
+        }
+    }
+
+    pub fn paper_exchange(&self) -> Option<std::sync::Arc<paper::PaperExchange<TConnector>>> {
+        match &self.mode {
+            Mode::Live { .. } => None,
+            Mode::Paper(exchange) => Some(exchange.clone()),
+        }
+    }
+
+    pub fn override_price_band(&self, enabled: bool) {
+        match &self.mode {
+            Mode::Live { trader, .. } => trader.override_price_band(enabled),
+            Mode::Paper(_) => log::debug!("Price band is not used in paper mode"),
+        }
+    }
+
+    pub fn market_info(&self) -> std::sync::Arc<MarketInfo> {
+        self.market_info.clone()
+    }
+
+    pub fn listing_probe(&self) -> ListingProbe<TConnector> {
+        ListingProbe::new(self.client.clone(), self.market_info.clone())
+            .with_policy(self.policy.clone())
+    }
+
+    pub fn health(&self) -> agnostic::market::Future<HealthReport> {
+        let market_info = self.market_info.clone();
+        let trading_pair = converter::supported_coins()
+            .into_iter()
+            .map(|coins| TradingPair {
+                coins,
+                side: Side::Buy,
+                target: Target::Market,
+            })
+            .find(|trading_pair| market_info.is_enabled(trading_pair));
+        Box::pin(health::probe(self.api.clone(), self.sniffer.clone(), self.policy.clone(), trading_pair))
+    }
+
+    pub fn kill_switch(&self) -> KillSwitch {
+        match &self.mode {
+            Mode::Live { trader, .. } => KillSwitch::new(trader.kill_switch_flag(), trader.clone()),
+            Mode::Paper(exchange) => KillSwitch::new(exchange.kill_switch_flag(), exchange.clone()),
         }
     }
 }
 
+pub struct ChatexMerchantBuilder<TConnector> {
+    connector: TConnector,
+    config: ChatexConfig,
+    fees: FeeSchedule,
+    market_info: MarketInfo,
+    paper_balances: Vec<agnostic::currency::Currency>,
+    secret_provider: Option<std::sync::Arc<dyn SecretProvider>>,
+    account: Option<String>,
+    metrics: Option<std::sync::Arc<dyn MetricsRecorder>>,
+    token_cache: Option<std::sync::Arc<TokenCache>>,
+}
+
+impl<TConnector> ChatexMerchantBuilder<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    pub fn new(connector: TConnector, config: ChatexConfig) -> Self {
+        ChatexMerchantBuilder {
+            connector,
+            config,
+            fees: FeeSchedule::default(),
+            market_info: MarketInfo::default(),
+            paper_balances: Vec::new(),
+            secret_provider: None,
+            account: None,
+            metrics: None,
+            token_cache: None,
+        }
+    }
+
+    pub fn token_cache(self, cache: std::sync::Arc<TokenCache>) -> ChatexMerchantBuilder<TokenCachingConnector<TConnector>> {
+        ChatexMerchantBuilder {
+            connector: TokenCachingConnector::new(self.connector, cache.clone()),
+            config: self.config,
+            fees: self.fees,
+            market_info: self.market_info,
+            paper_balances: self.paper_balances,
+            secret_provider: self.secret_provider,
+            account: self.account,
+            metrics: self.metrics,
+            token_cache: Some(cache),
+        }
+    }
+
+    pub fn metrics(mut self, metrics: std::sync::Arc<dyn MetricsRecorder>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn account(mut self, account: &str) -> Self {
+        self.account = Some(account.to_owned());
+        self
+    }
+
+    pub fn secret_provider(mut self, provider: std::sync::Arc<dyn SecretProvider>) -> Self {
+        self.secret_provider = Some(provider);
+        self
+    }
+
+    pub fn fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    pub fn market_info(mut self, market_info: MarketInfo) -> Self {
+        self.market_info = market_info;
+        self
+    }
+
+    pub fn paper_balances(mut self, balances: Vec<agnostic::currency::Currency>) -> Self {
+        self.paper_balances = balances;
+        self
+    }
+
+    pub fn build(self) -> Result<ChatexMerchant<TConnector>, String> {
+        let config = self.config;
+        config.validate()?;
+        let secret = match &self.secret_provider {
+            Some(provider) => provider.secret()?,
+            None => config.secret.secret()?,
+        };
+        let uri: hyper::Uri = config
+            .base_url
+            .parse()
+            .map_err(|error| format!("Invalid base url {}: {}", config.base_url, error))?;
+        let client = std::sync::Arc::new(chatex_sdk_rust::ChatexClient::new(
+            self.connector.clone(),
+            uri.clone(),
+            secret.expose().to_owned()));
+        let api = std::sync::Arc::new(ChatexApi::new(self.connector, uri, secret));
+        let mut policy = config.request_policy();
+        if let Some(metrics) = self.metrics.clone() {
+            policy.metrics = metrics;
+        }
+        policy.token_cache = self.token_cache.clone();
+        let policy = std::sync::Arc::new(policy);
+        let market_info = match config.pairs.clone() {
+            Some(pairs) => self.market_info.with_enabled_pairs(pairs),
+            None => self.market_info,
+        };
+        let market_info = std::sync::Arc::new(market_info);
+        let sniffer = std::sync::Arc::new(
+            sniffer::ChatexSniffer::new(client.clone())
+                .with_policy(policy.clone())
+                .with_exclude_own_orders(config.exclude_own_orders)
+                .with_book_depth(config.book_depth));
+        let mode = if config.paper {
+            let exchange = paper::PaperExchange::new(sniffer.clone(), self.paper_balances)
+                .with_price_epsilon(config.price_epsilon)
+                .with_matching(config.rate_tolerance, config.book_depth);
+            Mode::Paper(std::sync::Arc::new(exchange))
+        } else {
+            let mut trader = trader::ChatexTrader::new(std::sync::Arc::new(client.exchange()))
+                .with_market_info(market_info.clone(), config.rounding())
+                .with_matching(config.rate_tolerance, config.book_depth)
+                .with_exclude_own_orders(config.exclude_own_orders)
+                .with_policy(policy.clone());
+            if let Some(price_band) = config.price_band() {
+                trader = trader.with_price_band(price_band);
+            }
+            let accountant = accountant::ChatexAccountant::new(client.clone())
+                .with_fees(self.fees)
+                .with_price_epsilon(config.price_epsilon)
+                .with_policy(policy.clone());
+            Mode::Live {
+                accountant: std::sync::Arc::new(accountant),
+                trader: std::sync::Arc::new(trader),
+            }
+        };
+        log::info!("Chatex merchant built for {} (paper: {})", config.base_url, config.paper);
+        let merchant = ChatexMerchant {
+            sniffer,
+            mode,
+            market_info,
+            client,
+            policy,
+            api: Some(api),
+            account: None,
+            id: std::sync::Arc::from(ID),
+        };
+        Ok(match &self.account {
+            Some(account) => merchant.with_account(account),
+            None => merchant,
+        })
+    }
+
+    pub fn connect(self) -> agnostic::market::Future<Result<ChatexMerchant<TConnector>, String>> {
+        let merchant = self.build();
+        let future = async move {
+            let merchant = merchant?;
+            merchant.listing_probe().probe().await?;
+            Ok(merchant)
+        };
+        Box::pin(future)
+    }
+}
+
 impl<TConnector> agnostic::merchant::Merchant for ChatexMerchant<TConnector>
 where
     TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
 {
+    // `Merchant::id` must be `'static`, so accounts are told apart by `account_id`.
     fn id(&self) -> &'static str {
-        "Chatex"
+        ID
     }
 
     fn accountant(&self) -> std::sync::Arc<dyn agnostic::market::Accountant> {
-        self.accountant.clone()
+        match &self.mode {
+            Mode::Live { accountant, .. } => accountant.clone(),
+            Mode::Paper(exchange) => exchange.clone(),
+        }
     }
 
     fn trader(&self) -> std::sync::Arc<dyn agnostic::market::Trader> {
-        self.trader.clone()
+        match &self.mode {
+            Mode::Live { trader, .. } => trader.clone(),
+            Mode::Paper(exchange) => exchange.clone(),
+        }
     }
 
     fn sniffer(&self) -> std::sync::Arc<dyn agnostic::market::Sniffer> {
-        self.sniffer.clone()
+        match &self.mode {
+            Mode::Live { .. } => self.sniffer.clone(),
+            Mode::Paper(exchange) => exchange.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChatexMerchant;
+    use crate::config::{ChatexConfig, SecretSource};
+    use crate::secret::Secret;
+    use crate::test::{TestCase, SECRET};
+    use agnostic::merchant::Merchant;
+    use agnostic::trading_pair::{Coins, Side, Target, TradingPair};
+
+    #[test]
+    fn build_from_config() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let create_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::POST)
+                .path("/exchange/orders");
+            then.status(201);
+        });
+        let trading_pair = TradingPair {
+            coins: Coins::TonUsdt,
+            side: Side::Buy,
+            target: Target::Limit,
+        };
+        let config = ChatexConfig {
+            base_url: test_case.server.base_url(),
+            secret: SecretSource::Value(Secret::new(SECRET)),
+            pairs: Some(vec!["BTC/USDT".to_owned()]),
+            ..ChatexConfig::default()
+        };
+        let merchant = ChatexMerchant::builder(hyper::client::HttpConnector::new(), config)
+            .build()
+            .expect("Failed to build the merchant");
+        assert!(!merchant.is_paper(), "Live mode is the default");
+        assert!(merchant.api.is_some(), "The builder must create the api");
+        assert!(!merchant.market_info().is_enabled(&trading_pair), "Only configured pairs are enabled");
+        let order = agnostic::order::Order {
+            trading_pair,
+            price: 1.0,
+            amount: 1.0,
+        };
+        let result = tokio_test::block_on(merchant.trader().create_order(order));
+        assert!(result.is_err(), "Orders for disabled pairs must be rejected");
+        auth_mock.assert_hits(0);
+        create_mock.assert_hits(0);
+        let paper = ChatexMerchant::builder(
+            hyper::client::HttpConnector::new(),
+            ChatexConfig {
+                secret: SecretSource::Env("CHATEX_AGNOSTIC_MISSING_SECRET".to_owned()),
+                paper: true,
+                ..ChatexConfig::default()
+            })
+            .secret_provider(std::sync::Arc::new(Secret::new(SECRET)))
+            .build()
+            .expect("Failed to build the paper merchant");
+        assert!(paper.is_paper(), "Paper mode must come from the config");
+    }
+
+    #[test]
+    fn connect_probes_listed_pairs() {
+        let chatex = crate::fake_server::FakeChatex::start(SECRET);
+        let config = ChatexConfig {
+            base_url: chatex.base_url(),
+            secret: SecretSource::Value(Secret::new(SECRET)),
+            ..ChatexConfig::default()
+        };
+        let merchant = tokio_test::block_on(
+            ChatexMerchant::builder(hyper::client::HttpConnector::new(), config).connect());
+        assert!(merchant.is_ok(), "Failed to connect: {:#?}", merchant.err());
+        let listed = merchant.unwrap().market_info().listed_pairs();
+        assert_eq!(
+            listed.map(|pairs| pairs.len()),
+            Some(crate::converter::supported_pairs().len()),
+            "Pairs must be listed at startup");
     }
 }
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+pub const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrderEvent {
+    Created,
+    Cancelled,
+    Filled,
+}
+
+impl OrderEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderEvent::Created => "created",
+            OrderEvent::Cancelled => "cancelled",
+            OrderEvent::Filled => "filled",
+        }
+    }
+}
+
+pub trait MetricsRecorder: Send + Sync {
+    fn record_latency(&self, operation: &str, latency: std::time::Duration);
+    fn increment_error(&self, kind: &str);
+    fn increment_orders(&self, event: OrderEvent, count: u64);
+    fn increment_retries(&self, operation: &str);
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopRecorder;
+
+impl MetricsRecorder for NoopRecorder {
+    fn record_latency(&self, _operation: &str, _latency: std::time::Duration) {}
+    fn increment_error(&self, _kind: &str) {}
+    fn increment_orders(&self, _event: OrderEvent, _count: u64) {}
+    fn increment_retries(&self, _operation: &str) {}
+}
+
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Debug, Default)]
+struct Metrics {
+    latency: BTreeMap<String, Histogram>,
+    errors: BTreeMap<String, u64>,
+    orders: BTreeMap<&'static str, u64>,
+    retries: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct PrometheusRecorder {
+    metrics: std::sync::Mutex<Metrics>,
+}
+
+impl PrometheusRecorder {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Metrics> {
+        self.metrics.lock().expect("Metrics are poisoned")
+    }
+
+    pub fn errors(&self, kind: &str) -> u64 {
+        self.lock().errors.get(kind).copied().unwrap_or_default()
+    }
+
+    pub fn orders(&self, event: OrderEvent) -> u64 {
+        self.lock().orders.get(event.as_str()).copied().unwrap_or_default()
+    }
+
+    pub fn retries(&self, operation: &str) -> u64 {
+        self.lock().retries.get(operation).copied().unwrap_or_default()
+    }
+
+    pub fn requests(&self, operation: &str) -> u64 {
+        self.lock().latency.get(operation).map_or(0, |histogram| histogram.count)
+    }
+
+    pub fn render(&self) -> String {
+        let metrics = self.lock();
+        let mut output = String::new();
+        writeln!(output, "# HELP chatex_request_latency_seconds Latency of Chatex API requests.").ok();
+        writeln!(output, "# TYPE chatex_request_latency_seconds histogram").ok();
+        for (operation, histogram) in metrics.latency.iter() {
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+                writeln!(
+                    output,
+                    "chatex_request_latency_seconds_bucket{{operation=\"{}\",le=\"{}\"}} {}",
+                    operation,
+                    bound,
+                    count).ok();
+            }
+            writeln!(
+                output,
+                "chatex_request_latency_seconds_bucket{{operation=\"{}\",le=\"+Inf\"}} {}",
+                operation,
+                histogram.count).ok();
+            writeln!(output, "chatex_request_latency_seconds_sum{{operation=\"{}\"}} {}", operation, histogram.sum).ok();
+            writeln!(output, "chatex_request_latency_seconds_count{{operation=\"{}\"}} {}", operation, histogram.count).ok();
+        }
+        writeln!(output, "# HELP chatex_errors_total Errors by kind.").ok();
+        writeln!(output, "# TYPE chatex_errors_total counter").ok();
+        for (kind, count) in metrics.errors.iter() {
+            writeln!(output, "chatex_errors_total{{kind=\"{}\"}} {}", kind, count).ok();
+        }
+        writeln!(output, "# HELP chatex_orders_total Orders by event.").ok();
+        writeln!(output, "# TYPE chatex_orders_total counter").ok();
+        for (event, count) in metrics.orders.iter() {
+            writeln!(output, "chatex_orders_total{{event=\"{}\"}} {}", event, count).ok();
+        }
+        writeln!(output, "# HELP chatex_retries_total Retried requests by operation.").ok();
+        writeln!(output, "# TYPE chatex_retries_total counter").ok();
+        for (operation, count) in metrics.retries.iter() {
+            writeln!(output, "chatex_retries_total{{operation=\"{}\"}} {}", operation, count).ok();
+        }
+        output
+    }
+}
+
+impl MetricsRecorder for PrometheusRecorder {
+    fn record_latency(&self, operation: &str, latency: std::time::Duration) {
+        let seconds = latency.as_secs_f64();
+        let mut metrics = self.lock();
+        let histogram = metrics.latency.entry(operation.to_owned()).or_default();
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter_mut()) {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+
+    fn increment_error(&self, kind: &str) {
+        *self.lock().errors.entry(kind.to_owned()).or_default() += 1;
+    }
+
+    fn increment_orders(&self, event: OrderEvent, count: u64) {
+        *self.lock().orders.entry(event.as_str()).or_default() += count;
+    }
+
+    fn increment_retries(&self, operation: &str) {
+        *self.lock().retries.entry(operation.to_owned()).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MetricsRecorder, OrderEvent, PrometheusRecorder};
+
+    #[test]
+    fn render_prometheus_text() {
+        let recorder = PrometheusRecorder::default();
+        recorder.record_latency("get_my_orders", std::time::Duration::from_millis(20));
+        recorder.record_latency("get_my_orders", std::time::Duration::from_secs(20));
+        recorder.increment_error("timeout");
+        recorder.increment_orders(OrderEvent::Cancelled, 3);
+        recorder.increment_retries("get_my_orders");
+        let text = recorder.render();
+        for line in [
+            "chatex_request_latency_seconds_bucket{operation=\"get_my_orders\",le=\"0.025\"} 1",
+            "chatex_request_latency_seconds_bucket{operation=\"get_my_orders\",le=\"+Inf\"} 2",
+            "chatex_request_latency_seconds_count{operation=\"get_my_orders\"} 2",
+            "chatex_errors_total{kind=\"timeout\"} 1",
+            "chatex_orders_total{event=\"cancelled\"} 3",
+            "chatex_retries_total{operation=\"get_my_orders\"} 1",
+        ].iter() {
+            assert!(text.lines().any(|rendered| rendered == *line), "Missing {} in:\n{}", line, text);
+        }
+    }
+}
@@ -3,76 +3,150 @@ use chatex_sdk_rust::coin::CoinPair;
 use agnostic::trading_pair::{TradingPairConverter, TradingPair};
 use std::str::FromStr;
 
-pub struct Order {
+pub struct ChatexOrder {
     pub id: Option<u32>,
     pub pair: CoinPair,
     pub rate: f64,
     pub amount: f64,
 }
 
-impl Order {
+pub struct NormalizedOrder {
+    pub id: Option<u32>,
+    pub trading_pair: TradingPair,
+    pub price: f64,
+    pub amount: f64,
+}
+
+fn convert(trading_pair: &TradingPair, price: f64, amount: f64) -> (f64, f64) {
+    let order_price = price.into();
+    let price = agnostic::price::convert_to_base_coin_price(
+        trading_pair.target.clone(),
+        trading_pair.side.clone(),
+        &order_price);
+    let amount = agnostic::price::convert_to_base_coin_amount(
+        trading_pair.target.clone(),
+        trading_pair.side.clone(),
+        &order_price,
+        amount);
+    (price, amount)
+}
+
+impl ChatexOrder {
     pub fn from_trade(
         trading_pair: &TradingPair,
         trade: chatex_sdk_rust::models::Trade,
-    ) -> Order {
-        Self::from(trade.id, &trading_pair, &trade.order.rate, &trade.amount)
+    ) -> ChatexOrder {
+        Self::from(trade.id, trading_pair, &trade.order.rate, &trade.amount)
     }
 
     pub fn from_raw(
-        trading_pair: &agnostic::trading_pair::TradingPair,
+        trading_pair: &TradingPair,
         order: &chatex_sdk_rust::models::Order
-    ) -> Order {
-        Self::from(order.id, &trading_pair, &order.rate, &order.amount)
+    ) -> ChatexOrder {
+        Self::from(order.id, trading_pair, &order.rate, &order.amount)
     }
 
     fn from(
         id: u32,
         trading_pair: &TradingPair,
-        price: &str,
+        rate: &str,
         amount: &str,
-    ) -> Order {
-        let price = f64::from_str(&price).unwrap();
-        let amount = f64::from_str(&amount).unwrap();
+    ) -> ChatexOrder {
         let converter = converter::TradingPairConverter::default();
-        let pair = converter.to_pair(trading_pair.clone());
-        let order_price = price.into();
-        let price = agnostic::price::convert_to_base_coin_price(
-            trading_pair.target.clone(),
-            trading_pair.side.clone(),
-            &order_price);
-        let amount = agnostic::price::convert_to_base_coin_amount(
-            trading_pair.target.clone(),
-            trading_pair.side.clone(),
-            &order_price,
-            amount);
-        Order {
+        ChatexOrder {
             id: Some(id),
-            pair,
-            rate: price,
-            amount
+            pair: converter.to_pair(trading_pair.clone()),
+            rate: f64::from_str(rate).unwrap(),
+            amount: f64::from_str(amount).unwrap(),
+        }
+    }
+
+    pub fn normalize(self, trading_pair: &TradingPair) -> NormalizedOrder {
+        let (price, amount) = convert(trading_pair, self.rate, self.amount);
+        NormalizedOrder {
+            id: self.id,
+            trading_pair: trading_pair.clone(),
+            price,
+            amount,
         }
     }
 }
 
-impl From<agnostic::order::Order> for Order {
-    fn from(order: agnostic::order::Order) -> Order {
+impl NormalizedOrder {
+    pub fn from_trade(
+        trading_pair: &TradingPair,
+        trade: chatex_sdk_rust::models::Trade,
+    ) -> NormalizedOrder {
+        ChatexOrder::from_trade(trading_pair, trade).normalize(trading_pair)
+    }
+
+    pub fn from_raw(
+        trading_pair: &TradingPair,
+        order: &chatex_sdk_rust::models::Order
+    ) -> NormalizedOrder {
+        ChatexOrder::from_raw(trading_pair, order).normalize(trading_pair)
+    }
+
+    pub fn to_chatex(&self) -> ChatexOrder {
         let converter = converter::TradingPairConverter::default();
-        let pair = converter.to_pair(order.trading_pair.clone());
-        let order_price = order.price.into();
-        let price = agnostic::price::convert_to_base_coin_price(
-            order.trading_pair.target.clone(),
-            order.trading_pair.side.clone(),
-            &order_price);
-        let amount = agnostic::price::convert_to_base_coin_amount(
-            order.trading_pair.target.clone(),
-            order.trading_pair.side.clone(),
-            &order_price,
-            order.amount);
-        Order {
+        let (rate, amount) = convert(&self.trading_pair, self.price, self.amount);
+        ChatexOrder {
+            id: self.id,
+            pair: converter.to_pair(self.trading_pair.clone()),
+            rate,
+            amount,
+        }
+    }
+}
+
+impl From<agnostic::order::Order> for NormalizedOrder {
+    fn from(order: agnostic::order::Order) -> NormalizedOrder {
+        NormalizedOrder {
             id: None,
-            pair,
-            rate: price,
-            amount
+            trading_pair: order.trading_pair,
+            price: order.price,
+            amount: order.amount,
+        }
+    }
+}
+
+impl From<NormalizedOrder> for agnostic::order::Order {
+    fn from(order: NormalizedOrder) -> agnostic::order::Order {
+        agnostic::order::Order {
+            trading_pair: order.trading_pair,
+            price: order.price,
+            amount: order.amount,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChatexOrder, NormalizedOrder};
+    use agnostic::trading_pair::{Coins, Side, Target, TradingPair};
+
+    #[test]
+    fn round_trip() {
+        for (side, target) in [
+            (Side::Buy, Target::Market),
+            (Side::Sell, Target::Market),
+            (Side::Buy, Target::Limit),
+            (Side::Sell, Target::Limit),
+        ].iter() {
+            let trading_pair = TradingPair {
+                coins: Coins::TonUsdt,
+                side: side.clone(),
+                target: target.clone(),
+            };
+            let order = NormalizedOrder::from(agnostic::order::Order {
+                trading_pair: trading_pair.clone(),
+                price: 2.0,
+                amount: 4.0,
+            });
+            let chatex_order: ChatexOrder = order.to_chatex();
+            let restored = chatex_order.normalize(&trading_pair);
+            assert!((restored.price - 2.0).abs() < 1e-9, "Invalid price: {}", restored.price);
+            assert!((restored.amount - 4.0).abs() < 1e-9, "Invalid amount: {}", restored.amount);
         }
     }
 }
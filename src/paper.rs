@@ -0,0 +1,437 @@
+use crate::converter;
+use crate::error::Error;
+use crate::kill_switch::{CancelAllOrders, KillSwitchFlag};
+use crate::sniffer::ChatexSniffer;
+use crate::trader::AMOUNT_EPSILON;
+use crate::wallet::Wallet;
+use agnostic::currency::Currency;
+use agnostic::market::Sniffer;
+use agnostic::order::{Order, OrderWithId};
+use agnostic::trade::{Trade, TradeResult};
+use agnostic::trading_pair::{Coin, Side, Target, TradingPair, TradingPairConverter};
+
+#[derive(Default)]
+struct PaperState {
+    wallet: Wallet,
+    orders: Vec<OrderWithId>,
+    next_id: u64,
+}
+
+impl PaperState {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("paper-{}", self.next_id)
+    }
+
+    fn remove_order(&mut self, id: &str) -> Option<OrderWithId> {
+        let index = self.orders.iter().position(|order| order.id == id)?;
+        let order = self.orders.remove(index);
+        let (coin, held) = Wallet::required(&order.trading_pair, order.price, order.amount);
+        self.wallet.release(&coin, held);
+        Some(order)
+    }
+}
+
+pub struct PaperExchange<TConnector> {
+    sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+    state: std::sync::Arc<std::sync::Mutex<PaperState>>,
+    kill_switch: std::sync::Arc<KillSwitchFlag>,
+    price_epsilon: f64,
+    rate_tolerance: f64,
+    book_depth: u32,
+}
+
+impl<TConnector> PaperExchange<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    pub fn new(
+        sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+        balances: Vec<Currency>,
+    ) -> PaperExchange<TConnector> {
+        PaperExchange {
+            sniffer,
+            state: std::sync::Arc::new(std::sync::Mutex::new(PaperState {
+                wallet: Wallet::new(balances),
+                ..PaperState::default()
+            })),
+            kill_switch: std::sync::Arc::new(KillSwitchFlag::default()),
+            price_epsilon: 0.0001,
+            rate_tolerance: 0.00005,
+            book_depth: 30,
+        }
+    }
+
+    pub fn with_price_epsilon(mut self, price_epsilon: f64) -> PaperExchange<TConnector> {
+        self.price_epsilon = price_epsilon;
+        self
+    }
+
+    pub fn with_matching(mut self, rate_tolerance: f64, book_depth: u32) -> PaperExchange<TConnector> {
+        self.rate_tolerance = rate_tolerance;
+        self.book_depth = book_depth;
+        self
+    }
+
+    pub fn kill_switch_flag(&self) -> std::sync::Arc<KillSwitchFlag> {
+        self.kill_switch.clone()
+    }
+
+    pub fn balances(&self) -> Vec<Currency> {
+        lock(&self.state).wallet.balances()
+    }
+
+    pub fn sync(&self) -> agnostic::market::Future<Result<Vec<TradeResult>, String>> {
+        let sniffer = self.sniffer.clone();
+        let state = self.state.clone();
+        let rate_tolerance = self.rate_tolerance;
+        Box::pin(async move { match_resting_orders(sniffer, state, rate_tolerance).await })
+    }
+}
+
+fn lock(
+    state: &std::sync::Mutex<PaperState>,
+) -> std::sync::MutexGuard<'_, PaperState> {
+    state.lock().expect("Paper state is poisoned")
+}
+
+async fn match_resting_orders<TConnector>(
+    sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+    state: std::sync::Arc<std::sync::Mutex<PaperState>>,
+    rate_tolerance: f64,
+) -> Result<Vec<TradeResult>, String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    let resting_orders = lock(&state).orders.clone();
+    let mut crossed = Vec::new();
+    for order in resting_orders {
+        let book_pair = TradingPair {
+            coins: order.trading_pair.coins.clone(),
+            side: order.trading_pair.side.clone(),
+            target: Target::Market,
+        };
+        let book = sniffer.all_the_best_orders(book_pair, 1).await?;
+        let is_crossed = match (book.first(), &order.trading_pair.side) {
+            (Some(best), Side::Buy) => best.price <= order.price + rate_tolerance,
+            (Some(best), Side::Sell) => best.price + rate_tolerance >= order.price,
+            (None, _) => false,
+        };
+        if is_crossed {
+            crossed.push(order.id);
+        }
+    }
+    let mut state = lock(&state);
+    let mut fills = Vec::with_capacity(crossed.len());
+    for id in crossed {
+        if let Some(order) = state.remove_order(&id) {
+            state.wallet.settle(&order.trading_pair, order.price, order.amount, 0.0);
+            log::info!("Paper order filled: {:#?}", order);
+            fills.push(TradeResult {
+                id: order.id,
+                trading_pair: order.trading_pair,
+                amount: order.amount,
+                price: order.price,
+            });
+        }
+    }
+    Ok(fills)
+}
+
+async fn execute_market_order<TConnector>(
+    sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+    state: std::sync::Arc<std::sync::Mutex<PaperState>>,
+    order: Order,
+    rate_tolerance: f64,
+    book_depth: u32,
+) -> Result<Trade, String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    let book = sniffer
+        .all_the_best_orders(order.trading_pair.clone(), book_depth)
+        .await?;
+    let mut remaining = order.amount;
+    let mut filled = 0.0;
+    let mut volume = 0.0;
+    for level in book.iter() {
+        let is_acceptable = match order.trading_pair.side {
+            Side::Buy => level.price <= order.price + rate_tolerance,
+            Side::Sell => level.price + rate_tolerance >= order.price,
+        };
+        if remaining <= AMOUNT_EPSILON || !is_acceptable {
+            break;
+        }
+        let amount = remaining.min(level.amount);
+        filled += amount;
+        volume += amount * level.price;
+        remaining -= amount;
+    }
+    if filled <= AMOUNT_EPSILON {
+        return Err(format!("Failed to find the order: {:#?}", order));
+    }
+    let price = volume / filled;
+    let mut state = lock(&state);
+    let (coin, required) = Wallet::required(&order.trading_pair, price, filled);
+    state.wallet.ensure(&coin, required)?;
+    state.wallet.settle(&order.trading_pair, price, filled, 0.0);
+    let trade = TradeResult {
+        id: state.next_id(),
+        trading_pair: order.trading_pair,
+        amount: filled,
+        price,
+    };
+    log::info!("Paper trade: {:#?}", trade);
+    Ok(Trade::Market(trade))
+}
+
+fn place_limit_order(
+    state: &std::sync::Mutex<PaperState>,
+    order: Order,
+) -> Result<Trade, String> {
+    let mut state = lock(state);
+    let order = OrderWithId {
+        id: state.next_id(),
+        trading_pair: order.trading_pair,
+        price: order.price,
+        amount: order.amount,
+    };
+    let (coin, held) = Wallet::required(&order.trading_pair, order.price, order.amount);
+    state.wallet.hold(&coin, held)?;
+    log::info!("Paper order created: {:#?}", order);
+    state.orders.push(order.clone());
+    Ok(Trade::Limit(order))
+}
+
+impl<TConnector> agnostic::market::Trader for PaperExchange<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    fn create_order(
+        &self,
+        order: Order,
+    ) -> agnostic::market::Future<Result<Trade, String>> {
+        let sniffer = self.sniffer.clone();
+        let state = self.state.clone();
+        let kill_switch = self.kill_switch.clone();
+        let rate_tolerance = self.rate_tolerance;
+        let book_depth = self.book_depth;
+        let future = async move {
+            if kill_switch.is_triggered() {
+                return Err(Error::KillSwitchTriggered.into());
+            }
+            match order.trading_pair.target {
+                Target::Market => execute_market_order(sniffer, state, order, rate_tolerance, book_depth).await,
+                Target::Limit => place_limit_order(&state, order),
+            }
+        };
+        Box::pin(future)
+    }
+
+    fn delete_order(&self, id: &str) -> agnostic::market::Future<Result<(), String>> {
+        let state = self.state.clone();
+        let id = id.to_owned();
+        let future = async move {
+            match lock(&state).remove_order(&id) {
+                Some(order) => {
+                    log::debug!("Paper order deleted: {:#?}", order);
+                    Ok(())
+                }
+                None => Err(format!("Paper order not found: {}", id)),
+            }
+        };
+        Box::pin(future)
+    }
+}
+
+impl<TConnector> CancelAllOrders for PaperExchange<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    fn cancel_all_orders(&self) -> agnostic::market::Future<Result<Vec<String>, String>> {
+        let state = self.state.clone();
+        let future = async move {
+            let mut state = lock(&state);
+            let ids: Vec<String> = state.orders.iter().map(|order| order.id.clone()).collect();
+            for id in ids.iter() {
+                state.remove_order(id);
+            }
+            Ok(ids)
+        };
+        Box::pin(future)
+    }
+}
+
+impl<TConnector> agnostic::market::Accountant for PaperExchange<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    fn ask(&self, coin: Coin) -> agnostic::market::Future<Result<Currency, String>> {
+        let sniffer = self.sniffer.clone();
+        let state = self.state.clone();
+        let rate_tolerance = self.rate_tolerance;
+        let future = async move {
+            match_resting_orders(sniffer, state.clone(), rate_tolerance).await?;
+            let balance = lock(&state).wallet.balance(&coin);
+            Ok(balance)
+        };
+        Box::pin(future)
+    }
+
+    fn ask_both(
+        &self,
+        left: Coin,
+        right: Coin,
+    ) -> agnostic::market::Future<Result<(Currency, Currency), String>> {
+        let sniffer = self.sniffer.clone();
+        let state = self.state.clone();
+        let rate_tolerance = self.rate_tolerance;
+        let future = async move {
+            match_resting_orders(sniffer, state.clone(), rate_tolerance).await?;
+            let state = lock(&state);
+            Ok((state.wallet.balance(&left), state.wallet.balance(&right)))
+        };
+        Box::pin(future)
+    }
+
+    fn calculate_volume(&self, _trading_pair: TradingPair, price: f64, amount: f64) -> f64 {
+        price * amount
+    }
+
+    fn nearest_price(&self, _trading_pair: TradingPair, price: f64) -> f64 {
+        price - self.price_epsilon
+    }
+}
+
+impl<TConnector> agnostic::market::Sniffer for PaperExchange<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    fn all_the_best_orders(
+        &self,
+        trading_pair: TradingPair,
+        count: u32,
+    ) -> agnostic::market::Future<Result<Vec<Order>, String>> {
+        self.sniffer.all_the_best_orders(trading_pair, count)
+    }
+
+    fn get_my_orders(
+        &self,
+        trading_pair: TradingPair,
+    ) -> agnostic::market::Future<Result<Vec<OrderWithId>, String>> {
+        let sniffer = self.sniffer.clone();
+        let state = self.state.clone();
+        let rate_tolerance = self.rate_tolerance;
+        let future = async move {
+            match_resting_orders(sniffer, state.clone(), rate_tolerance).await?;
+            let converter = converter::TradingPairConverter::default();
+            let pair = converter.to_string(trading_pair);
+            Ok(lock(&state)
+                .orders
+                .iter()
+                .filter(|order| converter.to_string(order.trading_pair.clone()) == pair)
+                .cloned()
+                .collect())
+        };
+        Box::pin(future)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::TestCase;
+    use crate::test::SERDE_ERROR;
+    use crate::merchant::ChatexMerchant;
+    use agnostic::currency::Currency;
+    use agnostic::merchant::Merchant;
+    use agnostic::trade::Trade;
+    use agnostic::trading_pair::{TradingPair, Coin, Coins, Target, Side};
+
+    #[test]
+    fn market_order_against_sniffed_book() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path("/exchange/orders");
+            let order = chatex_sdk_rust::models::typed::Order::new(
+                chatex_sdk_rust::coin::CoinPair::new(
+                    chatex_sdk_rust::coin::Coin::TON,
+                    chatex_sdk_rust::coin::Coin::USDT),
+                2.0,
+                4.0);
+            let body: chatex_sdk_rust::models::Order = order.into();
+            let body = serde_json::to_string(&vec![body]).expect(SERDE_ERROR);
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body);
+        });
+        let trade_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::POST)
+                .path_contains("/trades");
+            then.status(201);
+        });
+        let merchant = ChatexMerchant::paper(test_case.client.clone(), vec![
+            Currency { coin: Coin::USDT, amount: 10.0, held: 0.0 },
+        ]);
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                target: Target::Market,
+                side: Side::Buy,
+            },
+            amount: 3.0,
+            price: 2.0,
+        };
+        let trade = tokio_test::block_on(merchant.trader().create_order(order));
+        match trade {
+            Ok(Trade::Market(trade)) => {
+                assert_eq!(trade.amount, 3.0, "Invalid amount");
+                assert_eq!(trade.price, 2.0, "Invalid price");
+            },
+            other => panic!("Invalid paper trade: {:#?}", other),
+        }
+        let balances = tokio_test::block_on(
+            merchant.accountant().ask_both(Coin::TON, Coin::USDT));
+        assert!(balances.is_ok(), format!("Failed to get balances: {:#?}", balances.err()));
+        let (ton, usdt) = balances.unwrap();
+        assert_eq!(ton.amount, 3.0, "Invalid TON balance");
+        assert_eq!(usdt.amount, 4.0, "Invalid USDT balance");
+        auth_mock.assert();
+        orders_mock.assert();
+        trade_mock.assert_hits(0);
+    }
+
+    #[test]
+    fn paper_matching_follows_the_config() {
+        let chatex = crate::fake_server::FakeChatex::start(crate::test::SECRET);
+        chatex.add_order(
+            chatex_sdk_rust::coin::CoinPair::new(
+                chatex_sdk_rust::coin::Coin::TON,
+                chatex_sdk_rust::coin::Coin::USDT),
+            2.0,
+            4.0);
+        let config = crate::config::ChatexConfig {
+            base_url: chatex.base_url(),
+            secret: crate::config::SecretSource::Value(crate::secret::Secret::new(crate::test::SECRET)),
+            paper: true,
+            rate_tolerance: 0.5,
+            ..crate::config::ChatexConfig::default()
+        };
+        let merchant = ChatexMerchant::builder(hyper::client::HttpConnector::new(), config)
+            .paper_balances(vec![Currency { coin: Coin::USDT, amount: 10.0, held: 0.0 }])
+            .build()
+            .expect("Failed to build the merchant");
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                target: Target::Market,
+                side: Side::Buy,
+            },
+            amount: 1.0,
+            price: 1.7,
+        };
+        let trade = tokio_test::block_on(merchant.trader().create_order(order));
+        assert!(trade.is_ok(), "The configured rate tolerance must apply: {:#?}", trade.err());
+    }
+}
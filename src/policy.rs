@@ -0,0 +1,222 @@
+use crate::error::Error;
+use crate::metrics::{MetricsRecorder, NoopRecorder};
+use crate::token::TokenCache;
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: std::time::Duration,
+    next: std::sync::Mutex<std::time::Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> RateLimiter {
+        RateLimiter {
+            interval: std::time::Duration::from_secs_f64(1.0 / requests_per_second),
+            next: std::sync::Mutex::new(std::time::Instant::now()),
+        }
+    }
+
+    pub async fn acquire(&self) {
+        let delay = {
+            let mut next = self.next.lock().expect("Rate limiter is poisoned");
+            let now = std::time::Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.interval;
+            slot - now
+        };
+        if delay > std::time::Duration::from_millis(0) {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+pub struct RequestPolicy {
+    pub timeout: Option<std::time::Duration>,
+    pub operation_timeouts: Vec<(String, std::time::Duration)>,
+    pub retries: u32,
+    pub retry_delay: std::time::Duration,
+    pub rate_limiter: Option<RateLimiter>,
+    pub metrics: std::sync::Arc<dyn MetricsRecorder>,
+    pub token_cache: Option<std::sync::Arc<TokenCache>>,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> RequestPolicy {
+        RequestPolicy {
+            timeout: None,
+            operation_timeouts: Vec::new(),
+            retries: 0,
+            retry_delay: std::time::Duration::default(),
+            rate_limiter: None,
+            metrics: std::sync::Arc::new(NoopRecorder),
+            token_cache: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for RequestPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestPolicy")
+            .field("timeout", &self.timeout)
+            .field("operation_timeouts", &self.operation_timeouts)
+            .field("retries", &self.retries)
+            .field("retry_delay", &self.retry_delay)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("token_cache", &self.token_cache.is_some())
+            .finish()
+    }
+}
+
+impl RequestPolicy {
+    pub fn timeout_for(&self, operation: &str) -> Option<std::time::Duration> {
+        self.operation_timeouts
+            .iter()
+            .find(|(name, _)| name == operation)
+            .map(|(_, timeout)| *timeout)
+            .or(self.timeout)
+    }
+
+    pub async fn read<T, E, F, TFuture>(&self, operation: &'static str, mut call: F) -> Result<T, Error>
+    where
+        F: FnMut() -> TFuture,
+        TFuture: std::future::Future<Output = Result<T, E>>,
+        E: std::fmt::Display + std::fmt::Debug,
+    {
+        let mut attempt = 0;
+        loop {
+            match self.authorized(operation, true, &mut call).await {
+                Ok(value) => return Ok(value),
+                Err(error) if attempt < self.retries => {
+                    attempt += 1;
+                    self.metrics.increment_retries(operation);
+                    log::warn!("{} failed, retry {}/{}: {}", operation, attempt, self.retries, error);
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    pub async fn write<T, E, F, TFuture>(&self, operation: &'static str, mut call: F) -> Result<T, Error>
+    where
+        F: FnMut() -> TFuture,
+        TFuture: std::future::Future<Output = Result<T, E>>,
+        E: std::fmt::Display + std::fmt::Debug,
+    {
+        self.authorized(operation, false, &mut call).await
+    }
+
+    fn unauthorized(&self) -> u64 {
+        self.token_cache.as_ref().map_or(0, |token_cache| token_cache.unauthorized())
+    }
+
+    // SDK errors carry no typed status, so a rejection is only known from the token cache.
+    // It may belong to a concurrent request, so only reads are sent again with a new token.
+    async fn authorized<T, E, F, TFuture>(
+        &self,
+        operation: &'static str,
+        is_read: bool,
+        call: &mut F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> TFuture,
+        TFuture: std::future::Future<Output = Result<T, E>>,
+        E: std::fmt::Display + std::fmt::Debug,
+    {
+        let unauthorized = self.unauthorized();
+        match self.once(operation, call()).await {
+            Err(Error::Api { message, .. }) if is_read && self.unauthorized() > unauthorized => {
+                self.metrics.increment_retries(operation);
+                log::warn!("{} was unauthorized, retrying with a new access token: {}", operation, message);
+                self.once(operation, call()).await
+            }
+            result => result,
+        }
+    }
+
+    async fn once<T, E, TFuture>(&self, operation: &'static str, call: TFuture) -> Result<T, Error>
+    where
+        TFuture: std::future::Future<Output = Result<T, E>>,
+        E: std::fmt::Display + std::fmt::Debug,
+    {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let started = std::time::Instant::now();
+        let result = match self.timeout_for(operation) {
+            Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                Ok(result) => result,
+                Err(_) => {
+                    let error = Error::Timeout {
+                        operation: operation.to_owned(),
+                        timeout_ms: timeout.as_millis() as u64,
+                    };
+                    self.metrics.record_latency(operation, started.elapsed());
+                    self.metrics.increment_error(error.kind());
+                    return Err(error);
+                }
+            },
+            None => call.await,
+        };
+        self.metrics.record_latency(operation, started.elapsed());
+        crate::instrument::debug(format_args!(
+            "{} request {} in {}ms",
+            operation,
+            if result.is_ok() { "succeeded" } else { "failed" },
+            started.elapsed().as_millis()));
+        result.map_err(|error| {
+            let kind = api_error_kind(&error);
+            self.metrics.increment_error(&kind);
+            Error::Api {
+                kind,
+                message: format!("{}", error),
+            }
+        })
+    }
+}
+
+// Labels SDK errors by their variant, e.g. `ChatexError::Hyper(..)` becomes `api_hyper`.
+fn api_error_kind<E: std::fmt::Debug>(error: &E) -> String {
+    let variant = format!("{:?}", error);
+    let mut kind = "api".to_owned();
+    for character in variant.chars().take_while(|character| character.is_ascii_alphanumeric()) {
+        if character.is_ascii_uppercase() || kind == "api" {
+            kind.push('_');
+        }
+        kind.push(character.to_ascii_lowercase());
+    }
+    kind
+}
+
+#[cfg(test)]
+mod test {
+    use super::{api_error_kind, RequestPolicy};
+    use crate::metrics::PrometheusRecorder;
+
+    #[derive(Debug)]
+    enum SdkError {
+        InvalidStatus(u16),
+    }
+
+    impl std::fmt::Display for SdkError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    #[test]
+    fn errors_are_labeled_by_kind() {
+        assert_eq!(api_error_kind(&SdkError::InvalidStatus(500)), "api_invalid_status");
+        assert_eq!(api_error_kind(&"failed".to_owned()), "api", "Unknown errors fall back to api");
+        let metrics = std::sync::Arc::new(PrometheusRecorder::default());
+        let policy = RequestPolicy {
+            metrics: metrics.clone(),
+            ..RequestPolicy::default()
+        };
+        let result = tokio_test::block_on(
+            policy.read("order_book", || async { Err::<(), _>(SdkError::InvalidStatus(500)) }));
+        assert!(result.is_err(), "The error must be passed through");
+        assert_eq!(metrics.errors("api_invalid_status"), 1, "The error must be counted by kind");
+        assert_eq!(metrics.requests("order_book"), 1, "The latency must be recorded by operation");
+    }
+}
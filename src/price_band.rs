@@ -0,0 +1,46 @@
+use crate::error::Error;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PriceBand {
+    pub max_deviation_percent: f64,
+}
+
+impl PriceBand {
+    pub fn new(max_deviation_percent: f64) -> PriceBand {
+        PriceBand { max_deviation_percent }
+    }
+
+    pub fn deviation_percent(rate: f64, best_rate: f64) -> f64 {
+        ((rate - best_rate) / best_rate).abs() * 100.0
+    }
+
+    pub fn check(&self, rate: f64, best_rate: f64) -> Result<(), Error> {
+        if !rate.is_finite()
+            || rate <= 0.0
+            || Self::deviation_percent(rate, best_rate) > self.max_deviation_percent
+        {
+            Err(Error::PriceOutOfBand {
+                rate,
+                best_rate,
+                max_deviation_percent: self.max_deviation_percent,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PriceBand;
+
+    #[test]
+    fn check() {
+        let price_band = PriceBand::new(5.0);
+        assert!(price_band.check(1.04, 1.0).is_ok());
+        assert!(price_band.check(0.96, 1.0).is_ok());
+        assert!(price_band.check(1.06, 1.0).is_err());
+        assert!(price_band.check(0.5, 2.0).is_err(), "Inverted price must be rejected");
+        assert!(price_band.check(-1.0, 1.0).is_err());
+    }
+}
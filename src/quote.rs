@@ -0,0 +1,134 @@
+use agnostic::trading_pair::{Side, TradingPair};
+
+const AMOUNT_EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Debug)]
+pub struct Quote {
+    pub trading_pair: TradingPair,
+    pub amount: f64,
+    pub filled_amount: f64,
+    pub average_price: Option<f64>,
+    pub worst_price: Option<f64>,
+    pub total_cost: f64,
+    pub is_sufficient: bool,
+}
+
+pub fn walk(trading_pair: TradingPair, orders: &[agnostic::order::Order], amount: f64) -> Quote {
+    let mut filled_amount = 0.0;
+    let mut total_cost = 0.0;
+    let mut worst_price: Option<f64> = None;
+    // Buyers walk the asks from the cheapest level and sellers walk the bids from the richest one.
+    let mut orders: Vec<&agnostic::order::Order> = orders.iter().collect();
+    orders.sort_by(|left, right| {
+        let ordering = left.price.partial_cmp(&right.price).unwrap_or(std::cmp::Ordering::Equal);
+        match trading_pair.side {
+            Side::Buy => ordering,
+            Side::Sell => ordering.reverse(),
+        }
+    });
+    for order in orders {
+        let remaining = amount - filled_amount;
+        if remaining <= AMOUNT_EPSILON {
+            break;
+        }
+        let taken = order.amount.min(remaining);
+        if taken <= 0.0 {
+            continue;
+        }
+        filled_amount += taken;
+        total_cost += taken * order.price;
+        worst_price = Some(match (worst_price, &trading_pair.side) {
+            (Some(worst), Side::Buy) => worst.max(order.price),
+            (Some(worst), Side::Sell) => worst.min(order.price),
+            (None, _) => order.price,
+        });
+    }
+    Quote {
+        trading_pair,
+        amount,
+        filled_amount,
+        average_price: if filled_amount > 0.0 {
+            Some(total_cost / filled_amount)
+        } else {
+            None
+        },
+        worst_price,
+        total_cost,
+        is_sufficient: filled_amount + AMOUNT_EPSILON >= amount,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::walk;
+    use crate::fake_server::FakeChatex;
+    use crate::test::SECRET;
+    use agnostic::trading_pair::{Coins, Side, Target, TradingPair};
+    use chatex_sdk_rust::coin;
+
+    fn trading_pair() -> TradingPair {
+        TradingPair {
+            coins: Coins::TonUsdt,
+            side: Side::Buy,
+            target: Target::Market,
+        }
+    }
+
+    fn sell_pair() -> TradingPair {
+        TradingPair {
+            side: Side::Sell,
+            ..trading_pair()
+        }
+    }
+
+    fn level(price: f64, amount: f64) -> agnostic::order::Order {
+        agnostic::order::Order {
+            trading_pair: trading_pair(),
+            price,
+            amount,
+        }
+    }
+
+    #[test]
+    fn walk_the_book() {
+        let book = vec![level(2.0, 1.0), level(3.0, 2.0), level(5.0, 10.0)];
+        let quote = walk(trading_pair(), &book, 2.0);
+        assert!(quote.is_sufficient, "The book is deep enough");
+        assert_eq!(quote.total_cost, 5.0, "Invalid total cost");
+        assert_eq!(quote.average_price, Some(2.5), "Invalid average price");
+        assert_eq!(quote.worst_price, Some(3.0), "Invalid worst price");
+        let quote = walk(trading_pair(), &book, 20.0);
+        assert!(!quote.is_sufficient, "The book is too thin");
+        assert_eq!(quote.filled_amount, 13.0, "Invalid filled amount");
+        assert_eq!(quote.worst_price, Some(5.0), "Invalid worst price");
+        let quote = walk(trading_pair(), &[], 1.0);
+        assert!(!quote.is_sufficient, "An empty book has no liquidity");
+        assert_eq!(quote.average_price, None, "An empty book has no price");
+    }
+
+    #[test]
+    fn walk_an_unsorted_book() {
+        let book = vec![level(5.0, 10.0), level(2.0, 1.0), level(3.0, 2.0)];
+        let quote = walk(trading_pair(), &book, 2.0);
+        assert_eq!(quote.total_cost, 5.0, "Buyers must take the cheapest levels first");
+        assert_eq!(quote.worst_price, Some(3.0), "Invalid worst buy price");
+        let quote = walk(sell_pair(), &book, 11.0);
+        assert_eq!(quote.total_cost, 53.0, "Sellers must take the richest levels first");
+        assert_eq!(quote.worst_price, Some(3.0), "Invalid worst sell price");
+    }
+
+    #[test]
+    fn quote_from_sniffer() {
+        let chatex = FakeChatex::start(SECRET);
+        let pair = coin::CoinPair::new(coin::Coin::TON, coin::Coin::USDT);
+        chatex.add_order(pair.clone(), 2.0, 1.0);
+        chatex.add_order(pair, 4.0, 1.0);
+        let sniffer = crate::sniffer::ChatexSniffer::new(chatex.client());
+        let quote = tokio_test::block_on(sniffer.quote(trading_pair(), 1.5));
+        assert!(quote.is_ok(), "Failed to quote: {:#?}", quote.err());
+        let quote = quote.unwrap();
+        assert!(quote.is_sufficient, "The book is deep enough");
+        assert_eq!(quote.total_cost, 4.0, "Invalid total cost");
+        assert_eq!(quote.worst_price, Some(4.0), "Invalid worst price");
+    }
+}
@@ -0,0 +1,269 @@
+use crate::snapshot::{Snapshot, CSV_HEADER};
+use crate::sniffer::ChatexSniffer;
+use agnostic::market::Sniffer;
+use agnostic::trading_pair::TradingPair;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    JsonLines,
+    Csv,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::JsonLines => "jsonl",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rotation {
+    pub max_bytes: Option<u64>,
+    pub max_age: Option<std::time::Duration>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecorderConfig {
+    pub directory: std::path::PathBuf,
+    pub prefix: String,
+    pub trading_pairs: Vec<TradingPair>,
+    pub depth: u32,
+    pub interval: std::time::Duration,
+    pub formats: Vec<Format>,
+    pub rotation: Rotation,
+}
+
+struct RotatingWriter {
+    format: Format,
+    file: Option<std::io::BufWriter<std::fs::File>>,
+    path: Option<std::path::PathBuf>,
+    opened_at: std::time::Instant,
+    written: u64,
+    index: u32,
+}
+
+impl RotatingWriter {
+    fn new(format: Format) -> RotatingWriter {
+        RotatingWriter {
+            format,
+            file: None,
+            path: None,
+            opened_at: std::time::Instant::now(),
+            written: 0,
+            index: 0,
+        }
+    }
+
+    fn should_rotate(&self, rotation: &Rotation) -> bool {
+        self.file.is_none()
+            || rotation.max_bytes.map_or(false, |max_bytes| self.written >= max_bytes)
+            || rotation.max_age.map_or(false, |max_age| self.opened_at.elapsed() >= max_age)
+    }
+
+    fn open(&mut self, config: &RecorderConfig, timestamp: u64) -> std::io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        self.index += 1;
+        let path = config.directory.join(format!(
+            "{}-{}-{}.{}",
+            config.prefix,
+            timestamp,
+            self.index,
+            self.format.extension()));
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        self.written = 0;
+        if self.format == Format::Csv {
+            writeln!(file, "{}", CSV_HEADER)?;
+            self.written += CSV_HEADER.len() as u64 + 1;
+        }
+        log::info!("Recording snapshots to {}", path.display());
+        self.file = Some(file);
+        self.path = Some(path);
+        self.opened_at = std::time::Instant::now();
+        Ok(())
+    }
+
+    fn write(&mut self, config: &RecorderConfig, snapshot: &Snapshot) -> std::io::Result<()> {
+        if self.should_rotate(&config.rotation) {
+            self.open(config, snapshot.timestamp)?;
+        }
+        let lines = match self.format {
+            Format::JsonLines => vec![snapshot.to_json_line()],
+            Format::Csv => snapshot.to_csv_rows(),
+        };
+        let file = self.file.as_mut().expect("Writer was just opened");
+        for line in lines {
+            writeln!(file, "{}", line)?;
+            self.written += line.len() as u64 + 1;
+        }
+        file.flush()
+    }
+}
+
+pub struct SnapshotRecorder<TConnector> {
+    sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+    config: RecorderConfig,
+    writers: std::sync::Arc<std::sync::Mutex<Vec<RotatingWriter>>>,
+    is_stopped: std::sync::Arc<AtomicBool>,
+}
+
+impl<TConnector> SnapshotRecorder<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    pub fn new(
+        sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+        config: RecorderConfig,
+    ) -> std::io::Result<SnapshotRecorder<TConnector>> {
+        std::fs::create_dir_all(&config.directory)?;
+        let writers = config.formats.iter().map(|format| RotatingWriter::new(*format)).collect();
+        Ok(SnapshotRecorder {
+            sniffer,
+            config,
+            writers: std::sync::Arc::new(std::sync::Mutex::new(writers)),
+            is_stopped: std::sync::Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn files(&self) -> Vec<std::path::PathBuf> {
+        self.writers
+            .lock()
+            .map(|writers| writers.iter().filter_map(|writer| writer.path.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn stop(&self) {
+        self.is_stopped.store(true, Ordering::SeqCst);
+    }
+
+    pub fn record_once(&self) -> agnostic::market::Future<Result<Vec<Snapshot>, String>> {
+        let sniffer = self.sniffer.clone();
+        let config = self.config.clone();
+        let writers = self.writers.clone();
+        Box::pin(async move { record(sniffer, config, writers).await })
+    }
+
+    pub fn run(&self) -> agnostic::market::Future<()> {
+        let sniffer = self.sniffer.clone();
+        let config = self.config.clone();
+        let writers = self.writers.clone();
+        let is_stopped = self.is_stopped.clone();
+        let future = async move {
+            while !is_stopped.load(Ordering::SeqCst) {
+                if let Err(error) = record(sniffer.clone(), config.clone(), writers.clone()).await {
+                    log::error!("Failed to record snapshots: {}", error);
+                }
+                tokio::time::sleep(config.interval).await;
+            }
+        };
+        Box::pin(future)
+    }
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+async fn record<TConnector>(
+    sniffer: std::sync::Arc<ChatexSniffer<TConnector>>,
+    config: RecorderConfig,
+    writers: std::sync::Arc<std::sync::Mutex<Vec<RotatingWriter>>>,
+) -> Result<Vec<Snapshot>, String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    let mut snapshots = Vec::with_capacity(config.trading_pairs.len());
+    for trading_pair in config.trading_pairs.iter() {
+        let orders = sniffer.all_the_best_orders(trading_pair.clone(), config.depth).await?;
+        snapshots.push(Snapshot {
+            timestamp: now_millis(),
+            trading_pair: trading_pair.clone(),
+            orders,
+        });
+    }
+    let mut writers = writers.lock().map_err(|error| error.to_string())?;
+    for writer in writers.iter_mut() {
+        for snapshot in snapshots.iter() {
+            writer
+                .write(&config, snapshot)
+                .map_err(|error| format!("Failed to write the snapshot: {}", error))?;
+        }
+    }
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Format, RecorderConfig, Rotation, SnapshotRecorder};
+    use crate::sniffer::ChatexSniffer;
+    use crate::test::TestCase;
+    use crate::test::SERDE_ERROR;
+    use agnostic::trading_pair::{Coins, Side, Target, TradingPair};
+
+    #[test]
+    fn record_with_rotation() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET);
+            let order = chatex_sdk_rust::models::typed::Order::new(
+                chatex_sdk_rust::coin::CoinPair::new(
+                    chatex_sdk_rust::coin::Coin::TON,
+                    chatex_sdk_rust::coin::Coin::USDT),
+                2.0,
+                4.0);
+            let body: chatex_sdk_rust::models::Order = order.into();
+            let body = serde_json::to_string(&vec![body]).expect(SERDE_ERROR);
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body);
+        });
+        let directory = std::env::temp_dir().join(format!(
+            "chatex_agnostic_recorder_{}",
+            std::process::id()));
+        std::fs::remove_dir_all(&directory).ok();
+        let config = RecorderConfig {
+            directory: directory.clone(),
+            prefix: "ton_usdt".to_owned(),
+            trading_pairs: vec![TradingPair {
+                coins: Coins::TonUsdt,
+                side: Side::Buy,
+                target: Target::Market,
+            }],
+            depth: 10,
+            interval: std::time::Duration::from_millis(1),
+            formats: vec![Format::JsonLines, Format::Csv],
+            rotation: Rotation {
+                max_bytes: Some(1),
+                max_age: None,
+            },
+        };
+        let sniffer = std::sync::Arc::new(ChatexSniffer::new(test_case.client.clone()));
+        let recorder = SnapshotRecorder::new(sniffer, config).expect("Failed to create the recorder");
+        for _ in 0..2 {
+            let snapshots = tokio_test::block_on(recorder.record_once());
+            assert!(snapshots.is_ok(), format!("Failed to record: {:#?}", snapshots.err()));
+        }
+        let files = std::fs::read_dir(&directory).expect("Failed to read the directory").count();
+        assert_eq!(files, 4, "Every write must rotate both files");
+        let files = recorder.files();
+        let json = std::fs::read_to_string(&files[0]).expect("Failed to read the json file");
+        let snapshot = crate::snapshot::Snapshot::from_json_line(json.trim())
+            .expect("Failed to parse the snapshot");
+        assert_eq!(snapshot.orders.len(), 1, "Invalid amount of orders");
+        assert_eq!(snapshot.orders[0].price, 2.0, "Invalid price");
+        let csv = std::fs::read_to_string(&files[1]).expect("Failed to read the csv file");
+        assert_eq!(csv.lines().count(), 2, "Expected the header and one row");
+        auth_mock.assert_hits(2);
+        orders_mock.assert_hits(2);
+        std::fs::remove_dir_all(&directory).ok();
+    }
+}
@@ -0,0 +1,479 @@
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Exchange {
+    pub request: String,
+    pub response: String,
+}
+
+impl Exchange {
+    fn to_line(&self) -> String {
+        serde_json::json!({
+            "request": self.request,
+            "response": self.response,
+        }).to_string()
+    }
+
+    fn from_line(line: &str) -> std::io::Result<Exchange> {
+        let value: serde_json::Value = serde_json::from_str(line)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        let field = |name: &str| {
+            value
+                .get(name)
+                .and_then(|field| field.as_str())
+                .map(|field| field.to_owned())
+                .ok_or_else(|| std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Missing {} in the recorded exchange", name)))
+        };
+        Ok(Exchange {
+            request: field("request")?,
+            response: field("response")?,
+        })
+    }
+
+    fn matches(&self, request: &str) -> bool {
+        request_line(&self.request) == request_line(request)
+            && message_body(&self.request) == message_body(request)
+    }
+}
+
+pub fn read_exchanges(path: impl AsRef<std::path::Path>) -> std::io::Result<Vec<Exchange>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Exchange::from_line)
+        .collect()
+}
+
+pub(crate) fn request_line(message: &str) -> &str {
+    message.split("\r\n").next().unwrap_or_default()
+}
+
+pub(crate) fn message_body(message: &str) -> &str {
+    match message.find("\r\n\r\n") {
+        Some(index) => &message[index + 4..],
+        None => "",
+    }
+}
+
+pub(crate) fn message_complete(message: &[u8]) -> bool {
+    let header_end = match message.windows(4).position(|window| window == b"\r\n\r\n") {
+        Some(index) => index + 4,
+        None => return false,
+    };
+    let headers = String::from_utf8_lossy(&message[..header_end]);
+    let mut content_length = None;
+    let mut is_chunked = false;
+    for line in headers.split("\r\n").skip(1) {
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+        let value = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+        match name.as_str() {
+            "content-length" => content_length = value.parse::<usize>().ok(),
+            "transfer-encoding" => is_chunked = value.contains("chunked"),
+            _ => (),
+        }
+    }
+    if is_chunked {
+        message.ends_with(b"0\r\n\r\n")
+    } else {
+        message.len() >= header_end + content_length.unwrap_or(0)
+    }
+}
+
+struct Recorder {
+    file: std::sync::Mutex<std::fs::File>,
+    exchanges: std::sync::Mutex<Vec<Exchange>>,
+}
+
+impl Recorder {
+    fn record(&self, exchange: Exchange) {
+        let line = exchange.to_line();
+        match self.file.lock() {
+            Ok(mut file) => {
+                if let Err(error) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+                    log::error!("Failed to record the exchange: {}", error);
+                }
+            }
+            Err(error) => log::error!("Failed to record the exchange: {}", error),
+        }
+        if let Ok(mut exchanges) = self.exchanges.lock() {
+            exchanges.push(exchange);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RecordingConnector<TConnector> {
+    inner: TConnector,
+    recorder: std::sync::Arc<Recorder>,
+}
+
+impl<TConnector> RecordingConnector<TConnector> {
+    pub fn new(
+        inner: TConnector,
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<RecordingConnector<TConnector>> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(RecordingConnector {
+            inner,
+            recorder: std::sync::Arc::new(Recorder {
+                file: std::sync::Mutex::new(file),
+                exchanges: std::sync::Mutex::new(Vec::new()),
+            }),
+        })
+    }
+
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.recorder
+            .exchanges
+            .lock()
+            .map(|exchanges| exchanges.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl<TConnector> hyper::service::Service<hyper::Uri> for RecordingConnector<TConnector>
+where
+    TConnector: hyper::service::Service<hyper::Uri> + Send,
+    TConnector::Response: AsyncRead + AsyncWrite + hyper::client::connect::Connection + Unpin + Send + 'static,
+    TConnector::Future: Send + 'static,
+    TConnector::Error: Send + 'static,
+{
+    type Response = RecordingStream<TConnector::Response>;
+    type Error = TConnector::Error;
+    type Future = Pin<Box<
+        dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: hyper::Uri) -> Self::Future {
+        let recorder = self.recorder.clone();
+        let connecting = self.inner.call(uri);
+        Box::pin(async move {
+            let stream = connecting.await?;
+            Ok(RecordingStream {
+                inner: stream,
+                recorder,
+                request: Vec::new(),
+                response: Vec::new(),
+            })
+        })
+    }
+}
+
+pub struct RecordingStream<TStream> {
+    inner: TStream,
+    recorder: std::sync::Arc<Recorder>,
+    request: Vec<u8>,
+    response: Vec<u8>,
+}
+
+impl<TStream> RecordingStream<TStream> {
+    fn flush_exchange(&mut self) {
+        let request = std::mem::take(&mut self.request);
+        let response = std::mem::take(&mut self.response);
+        if request.is_empty() {
+            return;
+        }
+        let exchange = Exchange {
+            request: crate::secret::redact_authorization(&String::from_utf8_lossy(&request)),
+            response: crate::secret::redact_access_token(&String::from_utf8_lossy(&response)),
+        };
+        self.recorder.record(exchange);
+    }
+}
+
+impl<TStream> Drop for RecordingStream<TStream> {
+    fn drop(&mut self) {
+        self.flush_exchange();
+    }
+}
+
+impl<TStream> AsyncRead for RecordingStream<TStream>
+where
+    TStream: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            this.response.extend_from_slice(&buf.filled()[filled..]);
+            if message_complete(&this.response) {
+                this.flush_exchange();
+            }
+        }
+        poll
+    }
+}
+
+impl<TStream> AsyncWrite for RecordingStream<TStream>
+where
+    TStream: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            if !this.response.is_empty() {
+                this.flush_exchange();
+            }
+            this.request.extend_from_slice(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl<TStream> hyper::client::connect::Connection for RecordingStream<TStream>
+where
+    TStream: hyper::client::connect::Connection,
+{
+    fn connected(&self) -> hyper::client::connect::Connected {
+        self.inner.connected()
+    }
+}
+
+#[derive(Clone)]
+pub struct ReplayConnector {
+    exchanges: std::sync::Arc<std::sync::Mutex<Vec<Exchange>>>,
+}
+
+impl ReplayConnector {
+    pub fn new(exchanges: Vec<Exchange>) -> ReplayConnector {
+        ReplayConnector {
+            exchanges: std::sync::Arc::new(std::sync::Mutex::new(exchanges)),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<ReplayConnector> {
+        Ok(Self::new(read_exchanges(path)?))
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.exchanges.lock().map(|exchanges| exchanges.len()).unwrap_or_default()
+    }
+}
+
+impl hyper::service::Service<hyper::Uri> for ReplayConnector {
+    type Response = ReplayStream;
+    type Error = std::io::Error;
+    type Future = std::future::Ready<Result<ReplayStream, std::io::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: hyper::Uri) -> Self::Future {
+        std::future::ready(Ok(ReplayStream {
+            exchanges: self.exchanges.clone(),
+            request: Vec::new(),
+            response: Vec::new(),
+            position: 0,
+            error: None,
+            waker: None,
+        }))
+    }
+}
+
+pub struct ReplayStream {
+    exchanges: std::sync::Arc<std::sync::Mutex<Vec<Exchange>>>,
+    request: Vec<u8>,
+    response: Vec<u8>,
+    position: usize,
+    error: Option<String>,
+    waker: Option<std::task::Waker>,
+}
+
+impl ReplayStream {
+    fn respond(&mut self) {
+        let request = String::from_utf8_lossy(&self.request).into_owned();
+        self.request.clear();
+        let exchange = self.exchanges.lock().ok().and_then(|mut exchanges| {
+            let index = exchanges.iter().position(|exchange| exchange.matches(&request))?;
+            Some(exchanges.remove(index))
+        });
+        match exchange {
+            Some(exchange) => {
+                self.response = exchange.response.into_bytes();
+                self.position = 0;
+            }
+            None => {
+                self.error = Some(format!(
+                    "No recorded response for the request: {}",
+                    request_line(&request)));
+            }
+        }
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl AsyncRead for ReplayStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        if let Some(error) = this.error.take() {
+            return Poll::Ready(Err(std::io::Error::new(std::io::ErrorKind::NotFound, error)));
+        }
+        if this.position < this.response.len() {
+            let remaining = &this.response[this.position..];
+            let length = remaining.len().min(buf.remaining());
+            buf.put_slice(&remaining[..length]);
+            this.position += length;
+            return Poll::Ready(Ok(()));
+        }
+        this.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl AsyncWrite for ReplayStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        this.request.extend_from_slice(buf);
+        if message_complete(&this.request) {
+            this.respond();
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl hyper::client::connect::Connection for ReplayStream {
+    fn connected(&self) -> hyper::client::connect::Connected {
+        hyper::client::connect::Connected::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{message_complete, RecordingConnector, RecordingStream, ReplayConnector};
+    use crate::fake_server::FakeChatex;
+    use crate::merchant::ChatexMerchant;
+    use crate::test::SECRET;
+    use agnostic::merchant::Merchant;
+    use agnostic::trading_pair::{Coins, Side, Target, TradingPair};
+    use chatex_sdk_rust::coin;
+
+    fn best_orders<TConnector>(
+        merchant: &ChatexMerchant<TConnector>,
+    ) -> Vec<(f64, f64)>
+    where
+        TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+    {
+        let trading_pair = TradingPair {
+            coins: Coins::TonUsdt,
+            side: Side::Buy,
+            target: Target::Market,
+        };
+        let orders = tokio_test::block_on(merchant.sniffer().all_the_best_orders(trading_pair, 10));
+        assert!(orders.is_ok(), format!("Failed to get orders: {:#?}", orders.err()));
+        orders.unwrap().into_iter().map(|order| (order.price, order.amount)).collect()
+    }
+
+    #[test]
+    fn complete_messages_are_measured_in_bytes() {
+        let mut message = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n\xff".to_vec();
+        assert!(!message_complete(&message), "Invalid bytes must count as one byte each");
+        message.push(0xfe);
+        assert!(message_complete(&message), "The whole body is received");
+    }
+
+    #[test]
+    fn flush_clears_both_buffers() {
+        let path = std::env::temp_dir().join(format!(
+            "chatex_agnostic_flush_{}.jsonl",
+            std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let connector = RecordingConnector::new((), &path).expect("Failed to create the recording");
+        let mut stream = RecordingStream {
+            inner: (),
+            recorder: connector.recorder.clone(),
+            request: Vec::new(),
+            response: b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nstale".to_vec(),
+        };
+        stream.flush_exchange();
+        assert!(stream.response.is_empty(), "Responses without requests must be dropped");
+        stream.request = b"GET /exchange/orders HTTP/1.1\r\n\r\n".to_vec();
+        stream.response = b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n[]".to_vec();
+        stream.flush_exchange();
+        let exchanges = connector.exchanges();
+        assert_eq!(exchanges.len(), 1, "Only complete exchanges are recorded");
+        assert!(!exchanges[0].response.contains("stale"), "Stale bytes must not leak: {}", exchanges[0].response);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let path = std::env::temp_dir().join(format!(
+            "chatex_agnostic_replay_{}.jsonl",
+            std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let chatex = FakeChatex::start(SECRET);
+        chatex.add_order(coin::CoinPair::new(coin::Coin::TON, coin::Coin::USDT), 2.0, 4.0);
+        let connector = RecordingConnector::new(hyper::client::HttpConnector::new(), &path)
+            .expect("Failed to create the recording connector");
+        let client = std::sync::Arc::new(chatex_sdk_rust::ChatexClient::new(
+            connector.clone(),
+            chatex.base_url().parse().expect("Invalid url"),
+            SECRET.to_owned()));
+        let recorded = best_orders(&ChatexMerchant::new(client));
+        assert_eq!(connector.exchanges().len(), 2, "Invalid amount of recorded exchanges");
+        let recording = std::fs::read_to_string(&path).expect("Failed to read the recording");
+        assert!(!recording.contains(SECRET), "The secret leaked into the recording");
+        assert!(!recording.contains("fake-access-token"), "The access token leaked into the recording");
+        assert!(recording.contains(crate::secret::REDACTED), "The access token must be redacted");
+        drop(chatex);
+        let connector = ReplayConnector::from_file(&path)
+            .expect("Failed to read the recorded exchanges");
+        let client = std::sync::Arc::new(chatex_sdk_rust::ChatexClient::new(
+            connector.clone(),
+            "http://replay.invalid".parse().expect("Invalid url"),
+            SECRET.to_owned()));
+        let replayed = best_orders(&ChatexMerchant::new(client));
+        assert_eq!(recorded, replayed, "Replayed orders differ from the recorded ones");
+        assert_eq!(connector.remaining(), 0, "Not all exchanges were replayed");
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::BufRead;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+#[derive(Clone, PartialEq, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Secret {
+        Secret(secret.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", REDACTED)
+    }
+}
+
+pub trait SecretProvider: Send + Sync {
+    fn secret(&self) -> Result<Secret, String>;
+}
+
+impl SecretProvider for Secret {
+    fn secret(&self) -> Result<Secret, String> {
+        Ok(self.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvSecret(pub String);
+
+impl SecretProvider for EnvSecret {
+    fn secret(&self) -> Result<Secret, String> {
+        std::env::var(&self.0)
+            .map(Secret)
+            .map_err(|error| format!("Failed to read the secret from {}: {}", self.0, error))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileSecret(pub std::path::PathBuf);
+
+impl SecretProvider for FileSecret {
+    fn secret(&self) -> Result<Secret, String> {
+        std::fs::read_to_string(&self.0)
+            .map(|secret| Secret(secret.trim().to_owned()))
+            .map_err(|error| format!("Failed to read the secret from {}: {}", self.0.display(), error))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdinSecret;
+
+impl SecretProvider for StdinSecret {
+    fn secret(&self) -> Result<Secret, String> {
+        let mut secret = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut secret)
+            .map_err(|error| format!("Failed to read the secret from stdin: {}", error))?;
+        Ok(Secret(secret.trim().to_owned()))
+    }
+}
+
+pub struct CallbackSecret<F>(pub F);
+
+impl<F> SecretProvider for CallbackSecret<F>
+where
+    F: Fn() -> Result<String, String> + Send + Sync,
+{
+    fn secret(&self) -> Result<Secret, String> {
+        (self.0)().map(Secret)
+    }
+}
+
+pub fn redact_authorization(message: &str) -> String {
+    let header_end = message.find("\r\n\r\n").unwrap_or_else(|| message.len());
+    let headers = message[..header_end]
+        .split("\r\n")
+        .map(|line| match line.find(':') {
+            Some(index) if line[..index].trim().eq_ignore_ascii_case("authorization") => {
+                format!("{}: {}", &line[..index], REDACTED)
+            }
+            _ => line.to_owned(),
+        })
+        .collect::<Vec<String>>()
+        .join("\r\n");
+    format!("{}{}", headers, &message[header_end..])
+}
+
+fn unchunk(body: &str) -> Option<String> {
+    let mut decoded = String::new();
+    let mut rest = body;
+    loop {
+        let line_end = rest.find("\r\n")?;
+        let size = usize::from_str_radix(rest[..line_end].split(';').next()?.trim(), 16).ok()?;
+        if size == 0 {
+            return Some(decoded);
+        }
+        let chunk = rest.get(line_end + 2..line_end + 2 + size)?;
+        decoded.push_str(chunk);
+        rest = rest.get(line_end + 4 + size..)?;
+    }
+}
+
+pub fn redact_access_token(message: &str) -> String {
+    let header_end = match message.find("\r\n\r\n") {
+        Some(index) => index,
+        None => return message.to_owned(),
+    };
+    let headers = &message[..header_end];
+    let is_chunked = headers
+        .split("\r\n")
+        .any(|line| line.to_ascii_lowercase().starts_with("transfer-encoding") && line.contains("chunked"));
+    let body = &message[header_end + 4..];
+    let body = if is_chunked {
+        match unchunk(body) {
+            Some(body) => body,
+            None => return message.to_owned(),
+        }
+    } else {
+        body.to_owned()
+    };
+    let mut value: serde_json::Value = match serde_json::from_str(&body) {
+        Ok(value) => value,
+        Err(_) => return message.to_owned(),
+    };
+    match value.get_mut("access_token") {
+        Some(token) => *token = serde_json::Value::String(REDACTED.to_owned()),
+        None => return message.to_owned(),
+    }
+    let body = value.to_string();
+    if is_chunked {
+        return format!("{}\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n", headers, body.len(), body);
+    }
+    let headers = headers
+        .split("\r\n")
+        .map(|line| match line.find(':') {
+            Some(index) if line[..index].trim().eq_ignore_ascii_case("content-length") => {
+                format!("{}: {}", &line[..index], body.len())
+            }
+            _ => line.to_owned(),
+        })
+        .collect::<Vec<String>>()
+        .join("\r\n");
+    format!("{}\r\n\r\n{}", headers, body)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{redact_access_token, redact_authorization, CallbackSecret, FileSecret, Secret, SecretProvider};
+    use crate::test::SECRET;
+
+    #[test]
+    fn secret_is_redacted() {
+        let secret = Secret::new(SECRET);
+        assert!(!format!("{:?} {:#?} {}", secret, secret, secret).contains(SECRET), "Secret leaked");
+        assert_eq!(secret.expose(), SECRET);
+        let callback = CallbackSecret(|| Ok(SECRET.to_owned()));
+        assert_eq!(callback.secret(), Ok(secret));
+        let path = std::env::temp_dir().join(format!("chatex_agnostic_secret_{}", std::process::id()));
+        std::fs::write(&path, format!("{}\n", SECRET)).expect("Failed to write the secret");
+        let file = FileSecret(path.clone()).secret().expect("Failed to read the secret");
+        assert_eq!(file.expose(), SECRET, "The trailing newline must be trimmed");
+        std::fs::remove_file(&path).ok();
+        let request = format!("POST /auth HTTP/1.1\r\nauthorization: Bearer {}\r\n\r\nbody", SECRET);
+        let redacted = redact_authorization(&request);
+        assert!(!redacted.contains(SECRET), "Authorization header leaked");
+        assert!(redacted.ends_with("\r\n\r\nbody"), "Body must be kept");
+        let body = format!(r#"{{"access_token":"{}","expires_in":60}}"#, SECRET);
+        let response = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+        let redacted = redact_access_token(&response);
+        assert!(!redacted.contains(SECRET), "Access token leaked");
+        assert!(redacted.contains("expires_in"), "Other fields must be kept");
+        assert!(crate::replay::message_complete(redacted.as_bytes()), "Content length must match the body");
+        let response = format!("HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n", body.len(), body);
+        let redacted = redact_access_token(&response);
+        assert!(!redacted.contains(SECRET), "Chunked access token leaked");
+        assert!(redacted.ends_with("\r\n0\r\n\r\n"), "Chunked framing must be kept");
+    }
+}
@@ -0,0 +1,135 @@
+use crate::converter;
+use agnostic::order::Order;
+use agnostic::trading_pair::{Side, Target, TradingPair};
+
+pub const CSV_HEADER: &str = "timestamp,pair,side,target,level,price,amount";
+
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub timestamp: u64,
+    pub trading_pair: TradingPair,
+    pub orders: Vec<Order>,
+}
+
+impl Snapshot {
+    pub fn to_json_line(&self) -> String {
+        let orders: Vec<serde_json::Value> = self
+            .orders
+            .iter()
+            .map(|order| serde_json::json!([order.price, order.amount]))
+            .collect();
+        serde_json::json!({
+            "timestamp": self.timestamp,
+            "pair": converter::pair_key(&self.trading_pair.coins),
+            "side": side_to_str(&self.trading_pair.side),
+            "target": target_to_str(&self.trading_pair.target),
+            "orders": orders,
+        }).to_string()
+    }
+
+    pub fn to_csv_rows(&self) -> Vec<String> {
+        let pair = converter::pair_key(&self.trading_pair.coins);
+        let side = side_to_str(&self.trading_pair.side);
+        let target = target_to_str(&self.trading_pair.target);
+        self.orders
+            .iter()
+            .enumerate()
+            .map(|(level, order)| format!(
+                "{},{},{},{},{},{},{}",
+                self.timestamp,
+                pair,
+                side,
+                target,
+                level,
+                order.price,
+                order.amount))
+            .collect()
+    }
+
+    pub fn from_json_line(line: &str) -> Result<Snapshot, String> {
+        let value: serde_json::Value = serde_json::from_str(line)
+            .map_err(|error| format!("Invalid snapshot: {}", error))?;
+        let field = |name: &str| value
+            .get(name)
+            .ok_or_else(|| format!("Missing {} in the snapshot", name));
+        let timestamp = field("timestamp")?
+            .as_u64()
+            .ok_or_else(|| "Invalid snapshot timestamp".to_owned())?;
+        let side = field("side")?
+            .as_str()
+            .and_then(side_from_str)
+            .ok_or_else(|| "Invalid snapshot side".to_owned())?;
+        let target = field("target")?
+            .as_str()
+            .and_then(target_from_str)
+            .ok_or_else(|| "Invalid snapshot target".to_owned())?;
+        let pair = field("pair")?
+            .as_str()
+            .ok_or_else(|| "Invalid snapshot pair".to_owned())?;
+        let coins = converter::supported_coins()
+            .into_iter()
+            .find(|coins| converter::pair_key(coins) == pair)
+            .ok_or_else(|| format!("Unsupported snapshot pair: {}", pair))?;
+        let trading_pair = TradingPair { coins, side, target };
+        let orders = field("orders")?
+            .as_array()
+            .ok_or_else(|| "Invalid snapshot orders".to_owned())?
+            .iter()
+            .map(|order| match (
+                order.get(0).and_then(|price| price.as_f64()),
+                order.get(1).and_then(|amount| amount.as_f64()),
+            ) {
+                (Some(price), Some(amount)) => Ok(Order {
+                    trading_pair: trading_pair.clone(),
+                    price,
+                    amount,
+                }),
+                _ => Err("Invalid snapshot order".to_owned()),
+            })
+            .collect::<Result<Vec<Order>, String>>()?;
+        Ok(Snapshot {
+            timestamp,
+            trading_pair,
+            orders,
+        })
+    }
+}
+
+pub fn read_snapshots(path: impl AsRef<std::path::Path>) -> Result<Vec<Snapshot>, String> {
+    std::fs::read_to_string(path)
+        .map_err(|error| format!("Failed to read snapshots: {}", error))?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Snapshot::from_json_line)
+        .collect()
+}
+
+pub fn side_to_str(side: &Side) -> &'static str {
+    match side {
+        Side::Buy => "buy",
+        Side::Sell => "sell",
+    }
+}
+
+pub fn side_from_str(side: &str) -> Option<Side> {
+    match side {
+        "buy" => Some(Side::Buy),
+        "sell" => Some(Side::Sell),
+        _ => None,
+    }
+}
+
+pub fn target_to_str(target: &Target) -> &'static str {
+    match target {
+        Target::Market => "market",
+        Target::Limit => "limit",
+    }
+}
+
+pub fn target_from_str(target: &str) -> Option<Target> {
+    match target {
+        "market" => Some(Target::Market),
+        "limit" => Some(Target::Limit),
+        _ => None,
+    }
+}
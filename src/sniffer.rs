@@ -1,8 +1,124 @@
-use agnostic::trading_pair::TradingPair;
+use crate::error::Error;
+use crate::instrument::{instrument, Span};
+use agnostic::trading_pair::{Target, TradingPair};
 use agnostic::trading_pair::TradingPairConverter;
+use crate::order::ChatexOrder;
+use crate::policy::RequestPolicy;
+use crate::quote::Quote;
+use agnostic::market::Sniffer;
+use std::str::FromStr;
+
+pub(crate) const MY_ORDERS_PAGE: u32 = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsupportedOrder {
+    pub id: String,
+    pub pair: String,
+}
+
+#[derive(Default)]
+pub struct AllMyOrders {
+    pub orders: Vec<agnostic::order::OrderWithId>,
+    pub unsupported: Vec<UnsupportedOrder>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SniffedOrder {
+    pub id: String,
+    pub order: agnostic::order::Order,
+    pub original_amount: f64,
+    pub remaining_amount: f64,
+    pub status: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub is_mine: bool,
+}
+
+impl From<SniffedOrder> for agnostic::order::Order {
+    fn from(order: SniffedOrder) -> agnostic::order::Order {
+        order.order
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_owned())
+    }
+}
+
+fn sniffed_order(
+    trading_pair: &TradingPair,
+    order: &chatex_sdk_rust::models::Order,
+    is_mine: bool,
+) -> SniffedOrder {
+    let chatex_order = ChatexOrder::from_raw(trading_pair, order);
+    let original_amount = f64::from_str(&order.initial_amount)
+        .ok()
+        .map(|amount| ChatexOrder {
+            id: chatex_order.id,
+            pair: chatex_order.pair.clone(),
+            rate: chatex_order.rate,
+            amount,
+        }.normalize(trading_pair).amount);
+    let normalized = chatex_order.normalize(trading_pair);
+    SniffedOrder {
+        id: order.id.to_string(),
+        original_amount: original_amount.unwrap_or(normalized.amount),
+        remaining_amount: normalized.amount,
+        status: non_empty(&order.status),
+        created_at: non_empty(&order.created_at),
+        updated_at: non_empty(&order.updated_at),
+        is_mine,
+        order: normalized.into(),
+    }
+}
+
+pub(crate) async fn my_orders<TConnector>(
+    exchange: &chatex_sdk_rust::ExchangeClient<TConnector>,
+    policy: &RequestPolicy,
+    operation: &str,
+    pair: Option<&chatex_sdk_rust::coin::CoinPair>,
+) -> Result<Vec<chatex_sdk_rust::models::Order>, Error>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    let mut orders = Vec::new();
+    loop {
+        let offset = orders.len() as u32;
+        let page = policy
+            .read(operation, || exchange.get_my_orders(
+                pair.cloned(),
+                None,
+                Some(offset),
+                Some(MY_ORDERS_PAGE)))
+            .await?;
+        let is_last = (page.len() as u32) < MY_ORDERS_PAGE;
+        orders.extend(page);
+        if is_last {
+            return Ok(orders);
+        }
+    }
+}
+
+pub(crate) async fn my_order_ids<TConnector>(
+    exchange: &chatex_sdk_rust::ExchangeClient<TConnector>,
+    policy: &RequestPolicy,
+    pair: &chatex_sdk_rust::coin::CoinPair,
+) -> Result<std::collections::HashSet<u32>, String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    let orders = my_orders(exchange, policy, "get_my_orders", Some(pair)).await?;
+    Ok(orders.into_iter().map(|order| order.id).collect())
+}
 
 pub struct ChatexSniffer<TConnector> {
     client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>,
+    policy: std::sync::Arc<RequestPolicy>,
+    exclude_own_orders: bool,
+    book_depth: u32,
 }
 
 impl<TConnector> ChatexSniffer<TConnector>
@@ -10,7 +126,127 @@ where
     TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
 {
     pub fn new(client: std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>>) -> Self {
-        ChatexSniffer { client }
+        ChatexSniffer {
+            client,
+            policy: std::sync::Arc::new(RequestPolicy::default()),
+            exclude_own_orders: false,
+            book_depth: 30,
+        }
+    }
+
+    pub fn client(&self) -> std::sync::Arc<chatex_sdk_rust::ChatexClient<TConnector>> {
+        self.client.clone()
+    }
+
+    pub fn with_policy(mut self, policy: std::sync::Arc<RequestPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_exclude_own_orders(mut self, exclude_own_orders: bool) -> Self {
+        self.exclude_own_orders = exclude_own_orders;
+        self
+    }
+
+    pub fn with_book_depth(mut self, book_depth: u32) -> Self {
+        self.book_depth = book_depth;
+        self
+    }
+
+    pub fn quote(
+        &self,
+        trading_pair: TradingPair,
+        amount: f64,
+    ) -> agnostic::market::Future<Result<Quote, String>> {
+        let orders = self.all_the_best_orders(trading_pair.clone(), self.book_depth);
+        Box::pin(async move {
+            let orders = orders.await?;
+            let quote = crate::quote::walk(trading_pair, &orders, amount);
+            if !quote.is_sufficient {
+                log::warn!("Not enough liquidity to quote {}: {:?}", amount, quote);
+            }
+            Ok(quote)
+        })
+    }
+
+    pub fn sniff_best_orders(
+        &self,
+        trading_pair: TradingPair,
+        count: u32,
+    ) -> agnostic::market::Future<Result<Vec<SniffedOrder>, String>> {
+        let exchange = self.client.exchange();
+        let policy = self.policy.clone();
+        let span = Span::new("sniff_best_orders").trading_pair(&trading_pair);
+        let future = async move {
+            let converter = crate::converter::TradingPairConverter::default();
+            let pair = converter.to_pair(trading_pair.clone());
+            let orders = policy
+                .read("all_the_best_orders", || exchange.get_all_orders(pair.clone(), None, Some(count)))
+                .await?;
+            let my_ids = my_order_ids(&exchange, &policy, &pair).await?;
+            Ok(orders
+                .iter()
+                .map(|order| sniffed_order(&trading_pair, order, my_ids.contains(&order.id)))
+                .collect())
+        };
+        instrument(span, future)
+    }
+
+    pub fn sniff_my_orders(
+        &self,
+        trading_pair: TradingPair,
+    ) -> agnostic::market::Future<Result<Vec<SniffedOrder>, String>> {
+        let exchange = self.client.exchange();
+        let policy = self.policy.clone();
+        let span = Span::new("sniff_my_orders").trading_pair(&trading_pair);
+        let future = async move {
+            let converter = crate::converter::TradingPairConverter::default();
+            let pair = converter.to_pair(trading_pair.clone());
+            let orders = policy
+                .read("get_my_orders", || exchange.get_my_orders(Some(pair.clone()), None, None, None))
+                .await?;
+            Ok(orders
+                .iter()
+                .map(|order| sniffed_order(&trading_pair, order, true))
+                .collect())
+        };
+        instrument(span, future)
+    }
+
+    pub fn get_all_my_orders(&self) -> agnostic::market::Future<Result<AllMyOrders, String>> {
+        let exchange = self.client.exchange();
+        let policy = self.policy.clone();
+        let span = Span::new("get_all_my_orders");
+        let future = async move {
+            let orders = my_orders(&exchange, &policy, "get_my_orders", None).await?;
+            let converter = crate::converter::TradingPairConverter::default();
+            let mut all_orders = AllMyOrders::default();
+            for order in orders {
+                let pair = String::from(order.pair.clone());
+                let trading_pair = crate::converter::find_pair(&pair)
+                    .and_then(|pair| converter.from_pair(pair, Target::Limit));
+                match trading_pair {
+                    Some(trading_pair) => {
+                        let order = crate::order::NormalizedOrder::from_raw(&trading_pair, &order);
+                        all_orders.orders.push(agnostic::order::OrderWithId {
+                            id: format!("{}", order.id.unwrap()),
+                            trading_pair,
+                            amount: order.amount,
+                            price: order.price,
+                        });
+                    }
+                    None => {
+                        log::warn!("Skipping order {} with unsupported pair {}", order.id, pair);
+                        all_orders.unsupported.push(UnsupportedOrder {
+                            id: order.id.to_string(),
+                            pair,
+                        });
+                    }
+                }
+            }
+            Ok(all_orders)
+        };
+        instrument(span, future)
     }
 }
 
@@ -24,27 +260,41 @@ where
         count: u32,
     ) -> agnostic::market::Future<Result<Vec<agnostic::order::Order>, String>> {
         let exchange = self.client.exchange();
+        let policy = self.policy.clone();
+        let exclude_own_orders = self.exclude_own_orders;
+        let span = Span::new("all_the_best_orders").trading_pair(&trading_pair);
         let future = async move {
             let converter = crate::converter::TradingPairConverter::default();
             let pair = converter.to_pair(trading_pair.clone());
-            match exchange.get_all_orders(pair, None, Some(count)).await {
+            let my_ids = if exclude_own_orders {
+                my_order_ids(&exchange, &policy, &pair).await?
+            } else {
+                std::collections::HashSet::new()
+            };
+            let limit = count.saturating_add(my_ids.len() as u32);
+            let orders = policy
+                .read("all_the_best_orders", || exchange.get_all_orders(pair.clone(), None, Some(limit)))
+                .await;
+            match orders {
                 Ok(orders) => Ok(orders
                     .into_iter()
+                    .filter(|order| !my_ids.contains(&order.id))
+                    .take(count as usize)
                     .map(|order| {
-                        let order = crate::order::Order::from_raw(
+                        let order = crate::order::NormalizedOrder::from_raw(
                             &trading_pair,
                             &order);
                         agnostic::order::Order {
                             trading_pair: trading_pair.clone(),
-                            price: order.rate,
+                            price: order.price,
                             amount: order.amount,
                         }
                     })
                     .collect()),
-                Err(error) => Err(format!("{}", error)),
+                Err(error) => Err(error.into()),
             }
         };
-        Box::pin(future)
+        instrument(span, future)
     }
 
     fn get_my_orders(
@@ -52,28 +302,33 @@ where
         trading_pair: TradingPair,
     ) -> agnostic::market::Future<Result<Vec<agnostic::order::OrderWithId>, String>> {
         let exchange = self.client.exchange();
+        let policy = self.policy.clone();
+        let span = Span::new("get_my_orders").trading_pair(&trading_pair);
         let future = async move {
             let converter = crate::converter::TradingPairConverter::default();
             let pair = converter.to_pair(trading_pair.clone());
-            match exchange.get_my_orders(Some(pair), None, None, None).await {
+            let orders = policy
+                .read("get_my_orders", || exchange.get_my_orders(Some(pair.clone()), None, None, None))
+                .await;
+            match orders {
                 Ok(orders) => Ok(orders
                     .into_iter()
                     .map(|order| {
-                        let order = crate::order::Order::from_raw(
+                        let order = crate::order::NormalizedOrder::from_raw(
                             &trading_pair,
                             &order);
                         agnostic::order::OrderWithId {
                             id: format!("{}", order.id.unwrap()),
                             trading_pair: trading_pair.clone(),
                             amount: order.amount,
-                            price: order.rate,
+                            price: order.price,
                         }
                     })
                     .collect()),
-                Err(error) => Err(error.to_string()),
+                Err(error) => Err(error.into()),
             }
         };
-        Box::pin(future)
+        instrument(span, future)
     }
 }
 
@@ -82,6 +337,7 @@ mod test {
     use crate::test;
     use crate::test::TestCase;
     use crate::converter;
+    use crate::fake_server::FakeChatex;
     use agnostic::trading_pair::TradingPairConverter;
     use agnostic::market::Sniffer;
 
@@ -180,4 +436,260 @@ mod test {
         auth_mock.assert();
         mock.assert();
     }
+
+    #[test]
+    fn get_all_my_orders() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let direct_pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        let mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET);
+            let mut unsupported: chatex_sdk_rust::models::Order = chatex_sdk_rust::models::typed::Order::new(
+                chatex_sdk_rust::coin::CoinPair::new(
+                    chatex_sdk_rust::coin::Coin::BTC,
+                    chatex_sdk_rust::coin::Coin::USDT),
+                1.0,
+                1.0,
+            ).into();
+            unsupported.id = 3;
+            let mut sell: chatex_sdk_rust::models::Order = chatex_sdk_rust::models::typed::Order::new(
+                direct_pair.clone(),
+                2.0,
+                4.0,
+            ).into();
+            sell.id = 1;
+            let mut buy: chatex_sdk_rust::models::Order = chatex_sdk_rust::models::typed::Order::new(
+                direct_pair.reversed(),
+                0.5,
+                8.0,
+            ).into();
+            buy.id = 2;
+            let body = serde_json::to_string(&vec![sell, buy, unsupported]).expect(test::SERDE_ERROR);
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body);
+        });
+        let sniffer = create_sniffer(test_case.client.clone());
+        let all_orders = tokio_test::block_on(sniffer.get_all_my_orders());
+        assert!(all_orders.is_ok(), "Failed to get all my orders: {:#?}", all_orders.err());
+        let all_orders = all_orders.unwrap();
+        assert_eq!(all_orders.orders.len(), 2, "Invalid amount of orders");
+        let sell = &all_orders.orders[0];
+        assert_eq!(sell.id, "1", "Invalid id");
+        assert!(matches!(sell.trading_pair.side, agnostic::trading_pair::Side::Sell), "Direct limit orders sell");
+        let buy = &all_orders.orders[1];
+        assert_eq!(buy.id, "2", "Invalid id");
+        assert!(matches!(buy.trading_pair.side, agnostic::trading_pair::Side::Buy), "Reversed limit orders buy");
+        assert!(
+            matches!(buy.trading_pair.target, agnostic::trading_pair::Target::Limit),
+            "My orders are limit orders");
+        assert_eq!(all_orders.unsupported.len(), 1, "Unsupported orders must be reported");
+        assert_eq!(all_orders.unsupported[0].id, "3", "Invalid unsupported id");
+        auth_mock.assert();
+        mock.assert();
+    }
+
+    #[test]
+    fn get_all_my_orders_on_every_page() {
+        let test_case = TestCase::default();
+        let _auth_mock = test_case.mock_access_token();
+        let direct_pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        let first_page: Vec<chatex_sdk_rust::models::Order> = (0..super::MY_ORDERS_PAGE)
+            .map(|id| {
+                let mut order: chatex_sdk_rust::models::Order =
+                    chatex_sdk_rust::models::typed::Order::new(direct_pair.clone(), 2.0, 1.0).into();
+                order.id = id + 1;
+                order
+            })
+            .collect();
+        let mut unsupported: chatex_sdk_rust::models::Order = chatex_sdk_rust::models::typed::Order::new(
+            chatex_sdk_rust::coin::CoinPair::new(
+                chatex_sdk_rust::coin::Coin::BTC,
+                chatex_sdk_rust::coin::Coin::USDT),
+            1.0,
+            1.0,
+        ).into();
+        unsupported.id = 1000;
+        let mut supported: chatex_sdk_rust::models::Order =
+            chatex_sdk_rust::models::typed::Order::new(direct_pair.reversed(), 0.5, 8.0).into();
+        supported.id = 1001;
+        let first_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .query_param("offset", "0");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_string(&first_page).expect(test::SERDE_ERROR));
+        });
+        let second_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .query_param("offset", &super::MY_ORDERS_PAGE.to_string());
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_string(&vec![unsupported, supported]).expect(test::SERDE_ERROR));
+        });
+        let sniffer = create_sniffer(test_case.client.clone());
+        let all_orders = tokio_test::block_on(sniffer.get_all_my_orders()).expect("Failed to get all my orders");
+        assert_eq!(all_orders.orders.len(), super::MY_ORDERS_PAGE as usize + 1, "Invalid amount of orders");
+        assert!(all_orders.orders.iter().any(|order| order.id == "1001"), "Supported orders of the second page");
+        assert_eq!(all_orders.unsupported.len(), 1, "Unsupported orders of the second page");
+        assert_eq!(all_orders.unsupported[0].id, "1000", "Invalid unsupported id");
+        first_mock.assert();
+        second_mock.assert();
+    }
+
+    #[test]
+    fn sniff_best_orders() {
+        let chatex = FakeChatex::start(test::SECRET);
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        chatex.set_balance(chatex_sdk_rust::coin::Coin::TON, 10.0);
+        let other_id = chatex.add_order(pair.clone(), 2.0, 4.0);
+        let client = chatex.client();
+        let my_order = tokio_test::block_on(client.exchange().create_order(pair, 1.0, 3.0))
+            .expect("Failed to create my order");
+        let trading_pair = agnostic::trading_pair::TradingPair {
+            coins: agnostic::trading_pair::Coins::TonUsdt,
+            side: agnostic::trading_pair::Side::Buy,
+            target: agnostic::trading_pair::Target::Market,
+        };
+        let sniffer = create_sniffer(client);
+        let orders = tokio_test::block_on(sniffer.sniff_best_orders(trading_pair.clone(), 10));
+        assert!(orders.is_ok(), "Failed to sniff orders: {:#?}", orders.err());
+        let orders = orders.unwrap();
+        assert_eq!(orders.len(), 2, "Invalid amount of orders");
+        let other = orders.iter().find(|order| order.id == other_id.to_string()).expect("Missing other order");
+        assert!(!other.is_mine, "Other orders are not mine");
+        assert_eq!(other.remaining_amount, other.order.amount, "Remaining amount is the order amount");
+        assert!(other.original_amount >= other.remaining_amount, "Original amount can not be less than remaining");
+        let mine = orders.iter().find(|order| order.id == my_order.id.to_string()).expect("Missing my order");
+        assert!(mine.is_mine, "My order must be marked");
+        let plain: agnostic::order::Order = mine.clone().into();
+        assert_eq!(plain.price, mine.order.price, "The plain order must be preserved");
+        let my_orders = tokio_test::block_on(sniffer.sniff_my_orders(trading_pair)).expect("Failed to sniff my orders");
+        assert_eq!(my_orders.len(), 1, "Invalid amount of my orders");
+        assert!(my_orders[0].is_mine, "My orders are mine");
+    }
+
+    #[test]
+    fn sniffed_order_details() {
+        let test_case = TestCase::default();
+        let _auth_mock = test_case.mock_access_token();
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        let mut order: chatex_sdk_rust::models::Order =
+            chatex_sdk_rust::models::typed::Order::new(pair, 2.0, 4.0).into();
+        order.id = 7;
+        order.initial_amount = "6".to_owned();
+        order.status = "ACTIVE".to_owned();
+        order.created_at = "2021-03-01T10:00:00Z".to_owned();
+        order.updated_at = "2021-03-01T10:05:00Z".to_owned();
+        let body = serde_json::to_string(&vec![order]).expect(test::SERDE_ERROR);
+        let orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path("/exchange/orders");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body.clone());
+        });
+        let my_orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path("/exchange/my-orders");
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(body.clone());
+        });
+        let trading_pair = agnostic::trading_pair::TradingPair {
+            coins: agnostic::trading_pair::Coins::TonUsdt,
+            side: agnostic::trading_pair::Side::Buy,
+            target: agnostic::trading_pair::Target::Market,
+        };
+        let sniffer = create_sniffer(test_case.client.clone());
+        let orders = tokio_test::block_on(sniffer.sniff_best_orders(trading_pair, 10))
+            .expect("Failed to sniff orders");
+        assert_eq!(orders.len(), 1, "Invalid amount of orders");
+        let order = &orders[0];
+        assert_eq!(order.id, "7", "Invalid id");
+        assert!(order.is_mine, "The order is listed among my orders");
+        assert_eq!(order.status.as_deref(), Some("ACTIVE"), "Invalid status");
+        assert_eq!(order.created_at.as_deref(), Some("2021-03-01T10:00:00Z"), "Invalid creation time");
+        assert_eq!(order.updated_at.as_deref(), Some("2021-03-01T10:05:00Z"), "Invalid update time");
+        assert_eq!(order.remaining_amount, order.order.amount, "Remaining amount is the order amount");
+        assert_eq!(order.original_amount / order.remaining_amount, 1.5, "Invalid original amount");
+        orders_mock.assert();
+        my_orders_mock.assert();
+    }
+
+    #[test]
+    fn my_order_ids_are_paginated() {
+        let chatex = FakeChatex::start(test::SECRET);
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        let count = super::MY_ORDERS_PAGE as usize + 1;
+        for _ in 0..count {
+            chatex.add_my_order(pair.clone(), 2.0, 1.0);
+        }
+        chatex.add_order(pair.clone(), 2.0, 1.0);
+        let exchange = chatex.client().exchange();
+        let ids = tokio_test::block_on(super::my_order_ids(
+            &exchange,
+            &crate::policy::RequestPolicy::default(),
+            &pair));
+        assert_eq!(ids.map(|ids| ids.len()), Ok(count), "All pages of my orders must be read");
+    }
+
+    #[test]
+    fn exclude_own_orders() {
+        let chatex = FakeChatex::start(test::SECRET);
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        chatex.set_balance(chatex_sdk_rust::coin::Coin::TON, 10.0);
+        chatex.add_order(pair.clone(), 2.0, 4.0);
+        let client = chatex.client();
+        tokio_test::block_on(client.exchange().create_order(pair, 1.0, 1.5))
+            .expect("Failed to create my order");
+        let trading_pair = agnostic::trading_pair::TradingPair {
+            coins: agnostic::trading_pair::Coins::TonUsdt,
+            side: agnostic::trading_pair::Side::Buy,
+            target: agnostic::trading_pair::Target::Market,
+        };
+        let sniffer = create_sniffer(client.clone());
+        let orders = tokio_test::block_on(sniffer.all_the_best_orders(trading_pair.clone(), 1))
+            .expect("Failed to get orders");
+        assert_eq!(orders.len(), 1, "Invalid amount of orders");
+        assert_eq!(orders[0].price, 1.5, "My order is the best one by default");
+        let sniffer = create_sniffer(client.clone()).with_exclude_own_orders(true);
+        let orders = tokio_test::block_on(sniffer.all_the_best_orders(trading_pair.clone(), 1))
+            .expect("Failed to get orders");
+        assert_eq!(orders.len(), 1, "The limit must be kept after filtering");
+        assert_eq!(orders[0].price, 2.0, "My orders must be excluded");
+        let trader = crate::trader::ChatexTrader::new(std::sync::Arc::new(client.exchange()))
+            .with_exclude_own_orders(true);
+        let order = agnostic::order::Order {
+            trading_pair: trading_pair.clone(),
+            price: 1.5,
+            amount: 1.0,
+        };
+        let result = tokio_test::block_on(agnostic::market::Trader::create_order(&trader, order));
+        assert!(result.is_err(), "Self trades must be prevented");
+        assert_eq!(chatex.orders().len(), 2, "No order must be traded");
+        chatex.set_balance(chatex_sdk_rust::coin::Coin::USDT, 10.0);
+        let trader = crate::trader::ChatexTrader::new(std::sync::Arc::new(client.exchange()))
+            .with_matching(0.01, 1)
+            .with_exclude_own_orders(true);
+        let order = agnostic::order::Order {
+            trading_pair: trading_pair.clone(),
+            price: 2.0,
+            amount: 1.0,
+        };
+        let result = tokio_test::block_on(agnostic::market::Trader::create_order(&trader, order));
+        assert!(result.is_ok(), "The book depth must not count my orders");
+    }
 }
@@ -0,0 +1,527 @@
+use crate::replay::{message_body, message_complete, request_line};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+const ACCESS_TOKEN_PATH: &str = "/auth/access-token";
+
+struct CachedToken {
+    credentials: u64,
+    body: Vec<u8>,
+    valid_until: std::time::Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    token: Option<CachedToken>,
+    is_refreshing: bool,
+    waiters: Vec<std::task::Waker>,
+    refreshes: u64,
+    unauthorized: u64,
+}
+
+enum Acquire {
+    Cached(Vec<u8>),
+    Refresh,
+    Wait,
+}
+
+pub struct TokenCache {
+    state: std::sync::Mutex<CacheState>,
+    refresh_margin: std::time::Duration,
+    default_ttl: std::time::Duration,
+}
+
+impl Default for TokenCache {
+    fn default() -> TokenCache {
+        TokenCache::new(std::time::Duration::from_secs(30), std::time::Duration::from_secs(300))
+    }
+}
+
+impl TokenCache {
+    pub fn new(refresh_margin: std::time::Duration, default_ttl: std::time::Duration) -> TokenCache {
+        TokenCache {
+            state: std::sync::Mutex::new(CacheState::default()),
+            refresh_margin,
+            default_ttl,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().expect("Token cache is poisoned")
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.lock()
+            .token
+            .as_ref()
+            .map_or(false, |token| token.valid_until > std::time::Instant::now())
+    }
+
+    pub fn invalidate(&self) {
+        self.lock().token = None;
+    }
+
+    pub fn refreshes(&self) -> u64 {
+        self.lock().refreshes
+    }
+
+    pub fn unauthorized(&self) -> u64 {
+        self.lock().unauthorized
+    }
+
+    fn acquire(&self, credentials: u64, waker: &std::task::Waker) -> Acquire {
+        let mut state = self.lock();
+        match &state.token {
+            Some(token) if token.credentials == credentials && token.valid_until > std::time::Instant::now() => {
+                Acquire::Cached(token.body.clone())
+            }
+            _ if state.is_refreshing => {
+                state.waiters.push(waker.clone());
+                Acquire::Wait
+            }
+            _ => {
+                state.is_refreshing = true;
+                Acquire::Refresh
+            }
+        }
+    }
+
+    fn finish_refresh(&self, credentials: u64, body: Option<&str>) {
+        let token = body.map(|body| CachedToken {
+            credentials,
+            valid_until: std::time::Instant::now() + self.lifetime(body),
+            body: body.as_bytes().to_vec(),
+        });
+        let mut state = self.lock();
+        if token.is_some() {
+            state.refreshes += 1;
+            state.token = token;
+        }
+        state.is_refreshing = false;
+        for waker in state.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn lifetime(&self, body: &str) -> std::time::Duration {
+        let value: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or_default();
+        let ttl = value
+            .get("expires_in")
+            .and_then(|expires_in| expires_in.as_u64())
+            .or_else(|| value
+                .get("expires_at")
+                .and_then(|expires_at| expires_at.as_u64())
+                .map(|expires_at| expires_at.saturating_sub(now)))
+            .map_or(self.default_ttl, std::time::Duration::from_secs);
+        ttl.checked_sub(self.refresh_margin).unwrap_or_default()
+    }
+
+    fn reject(&self) {
+        let mut state = self.lock();
+        state.token = None;
+        state.unauthorized += 1;
+    }
+}
+
+/// Shares access tokens by intercepting HTTP/1 traffic below the SDK. Only
+/// `/auth/access-token` responses with a `content-length` are cached; chunked
+/// responses are passed through and every request refreshes the token again.
+#[derive(Clone)]
+pub struct TokenCachingConnector<TConnector> {
+    inner: TConnector,
+    cache: std::sync::Arc<TokenCache>,
+}
+
+impl<TConnector> TokenCachingConnector<TConnector> {
+    pub fn new(inner: TConnector, cache: std::sync::Arc<TokenCache>) -> TokenCachingConnector<TConnector> {
+        TokenCachingConnector { inner, cache }
+    }
+
+    pub fn cache(&self) -> std::sync::Arc<TokenCache> {
+        self.cache.clone()
+    }
+}
+
+impl<TConnector> hyper::service::Service<hyper::Uri> for TokenCachingConnector<TConnector>
+where
+    TConnector: hyper::service::Service<hyper::Uri> + Send,
+    TConnector::Response: AsyncRead + AsyncWrite + hyper::client::connect::Connection + Unpin + Send + 'static,
+    TConnector::Future: Send + 'static,
+    TConnector::Error: Send + 'static,
+{
+    type Response = TokenStream<TConnector::Response>;
+    type Error = TConnector::Error;
+    type Future = Pin<Box<
+        dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: hyper::Uri) -> Self::Future {
+        let cache = self.cache.clone();
+        let connecting = self.inner.call(uri);
+        Box::pin(async move {
+            let stream = connecting.await?;
+            Ok(TokenStream {
+                inner: stream,
+                cache,
+                request: Vec::new(),
+                outgoing: Vec::new(),
+                waiting: None,
+                local: Vec::new(),
+                position: 0,
+                pending: VecDeque::new(),
+                response: Vec::new(),
+            })
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pending {
+    AccessToken(u64),
+    Request,
+}
+
+pub struct TokenStream<TStream> {
+    inner: TStream,
+    cache: std::sync::Arc<TokenCache>,
+    request: Vec<u8>,
+    outgoing: Vec<u8>,
+    waiting: Option<Vec<u8>>,
+    local: Vec<u8>,
+    position: usize,
+    pending: VecDeque<Pending>,
+    response: Vec<u8>,
+}
+
+fn is_access_token_request(request: &str) -> bool {
+    let mut parts = request_line(request).split_whitespace();
+    parts.next() == Some("POST")
+        && parts.next().map_or(false, |path| path.ends_with(ACCESS_TOKEN_PATH))
+}
+
+fn credentials(request: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    request
+        .split("\r\n")
+        .skip(1)
+        .filter(|line| line.to_ascii_lowercase().starts_with("authorization:"))
+        .for_each(|line| line.hash(&mut hasher));
+    hasher.finish()
+}
+
+fn status_code(response: &str) -> Option<u16> {
+    request_line(response).split_whitespace().nth(1)?.parse().ok()
+}
+
+fn cached_response(body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+        body.len()).into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+impl<TStream> TokenStream<TStream>
+where
+    TStream: AsyncRead + AsyncWrite + Unpin,
+{
+    fn send_access_token_request(&mut self, request: Vec<u8>, cx: &mut Context<'_>) -> bool {
+        let credentials = credentials(&String::from_utf8_lossy(&request));
+        match self.cache.acquire(credentials, cx.waker()) {
+            Acquire::Cached(body) => {
+                self.local = cached_response(&body);
+                self.position = 0;
+                true
+            }
+            Acquire::Refresh => {
+                self.pending.push_back(Pending::AccessToken(credentials));
+                self.outgoing.extend_from_slice(&request);
+                true
+            }
+            Acquire::Wait => {
+                self.waiting = Some(request);
+                false
+            }
+        }
+    }
+
+    fn send(&mut self, cx: &mut Context<'_>) {
+        let request = std::mem::take(&mut self.request);
+        if is_access_token_request(&String::from_utf8_lossy(&request)) {
+            self.send_access_token_request(request, cx);
+        } else {
+            self.pending.push_back(Pending::Request);
+            self.outgoing.extend_from_slice(&request);
+        }
+    }
+
+    fn write_outgoing(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        while !self.outgoing.is_empty() {
+            match Pin::new(&mut self.inner).poll_write(cx, &self.outgoing) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(std::io::ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(written)) => {
+                    self.outgoing.drain(..written);
+                }
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn receive(&mut self, bytes: &[u8]) {
+        if self.pending.is_empty() {
+            return;
+        }
+        self.response.extend_from_slice(bytes);
+        if !message_complete(&self.response) {
+            return;
+        }
+        let response = String::from_utf8_lossy(&self.response).into_owned();
+        self.response.clear();
+        let status = status_code(&response).unwrap_or_default();
+        match self.pending.pop_front() {
+            Some(Pending::AccessToken(credentials)) => {
+                let is_chunked = response.to_ascii_lowercase().contains("transfer-encoding: chunked");
+                if is_chunked {
+                    log::warn!("Chunked access token responses are not cached");
+                }
+                if (200..300).contains(&status) && !is_chunked {
+                    self.cache.finish_refresh(credentials, Some(message_body(&response)));
+                } else {
+                    self.cache.finish_refresh(credentials, None);
+                }
+            }
+            Some(Pending::Request) if status == 401 => {
+                log::warn!("Access token was rejected, the cached token is dropped");
+                self.cache.reject();
+            }
+            _ => (),
+        }
+    }
+}
+
+impl<TStream> Drop for TokenStream<TStream> {
+    fn drop(&mut self) {
+        for pending in self.pending.iter() {
+            if let Pending::AccessToken(credentials) = pending {
+                self.cache.finish_refresh(*credentials, None);
+            }
+        }
+    }
+}
+
+impl<TStream> AsyncRead for TokenStream<TStream>
+where
+    TStream: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        if let Some(request) = this.waiting.take() {
+            if !this.send_access_token_request(request, cx) {
+                return Poll::Pending;
+            }
+        }
+        if this.position < this.local.len() {
+            let remaining = &this.local[this.position..];
+            let length = remaining.len().min(buf.remaining());
+            buf.put_slice(&remaining[..length]);
+            this.position += length;
+            return Poll::Ready(Ok(()));
+        }
+        if let Poll::Ready(Err(error)) = this.write_outgoing(cx) {
+            return Poll::Ready(Err(error));
+        }
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            this.receive(&buf.filled()[filled..]);
+        }
+        poll
+    }
+}
+
+impl<TStream> AsyncWrite for TokenStream<TStream>
+where
+    TStream: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        this.request.extend_from_slice(buf);
+        if message_complete(&this.request) {
+            this.send(cx);
+        }
+        if let Poll::Ready(Err(error)) = this.write_outgoing(cx) {
+            return Poll::Ready(Err(error));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        match this.write_outgoing(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+            poll => poll,
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        match this.write_outgoing(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_shutdown(cx),
+            poll => poll,
+        }
+    }
+}
+
+impl<TStream> hyper::client::connect::Connection for TokenStream<TStream>
+where
+    TStream: hyper::client::connect::Connection,
+{
+    fn connected(&self) -> hyper::client::connect::Connected {
+        self.inner.connected()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TokenCache, TokenCachingConnector};
+    use crate::policy::RequestPolicy;
+    use crate::sniffer::ChatexSniffer;
+    use crate::test::{TestCase, SECRET, SERDE_ERROR};
+    use agnostic::market::Sniffer;
+    use agnostic::trading_pair::{Coins, Side, Target, TradingPair};
+
+    fn create_sniffer(
+        test_case: &TestCase,
+        cache: std::sync::Arc<TokenCache>,
+    ) -> ChatexSniffer<TokenCachingConnector<hyper::client::HttpConnector>> {
+        let connector = TokenCachingConnector::new(hyper::client::HttpConnector::new(), cache.clone());
+        let client = std::sync::Arc::new(chatex_sdk_rust::ChatexClient::new(
+            connector,
+            test_case.server.base_url().parse().expect("Invalid url"),
+            SECRET.to_owned()));
+        let policy = RequestPolicy {
+            token_cache: Some(cache),
+            ..RequestPolicy::default()
+        };
+        ChatexSniffer::new(client).with_policy(std::sync::Arc::new(policy))
+    }
+
+    fn trading_pair() -> TradingPair {
+        TradingPair {
+            coins: Coins::TonUsdt,
+            side: Side::Sell,
+            target: Target::Market,
+        }
+    }
+
+    #[test]
+    fn shared_access_token() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET);
+            let body: Vec<chatex_sdk_rust::models::Order> = Vec::new();
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_string(&body).expect(SERDE_ERROR));
+        });
+        let cache = std::sync::Arc::new(TokenCache::default());
+        let sniffer = create_sniffer(&test_case, cache.clone());
+        let (first, second) = tokio_test::block_on(async {
+            tokio::join!(
+                sniffer.all_the_best_orders(trading_pair(), 10),
+                sniffer.get_my_orders(trading_pair()))
+        });
+        assert!(first.is_ok(), "Failed to get orders: {:#?}", first.err());
+        assert!(second.is_ok(), "Failed to get my orders: {:#?}", second.err());
+        let third = tokio_test::block_on(sniffer.all_the_best_orders(trading_pair(), 10));
+        assert!(third.is_ok(), "Failed to get orders: {:#?}", third.err());
+        assert!(cache.is_valid(), "The token must be cached");
+        assert_eq!(cache.refreshes(), 1, "Concurrent refreshes must be serialized");
+        auth_mock.assert_hits(1);
+        orders_mock.assert_hits(3);
+        cache.invalidate();
+        let fourth = tokio_test::block_on(sniffer.get_my_orders(trading_pair()));
+        assert!(fourth.is_ok(), "Failed to get my orders: {:#?}", fourth.err());
+        auth_mock.assert_hits(2);
+    }
+
+    #[test]
+    fn expiring_access_token_is_refreshed() {
+        let cache = TokenCache::new(std::time::Duration::from_secs(30), std::time::Duration::from_secs(300));
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Invalid time")
+            .as_secs();
+        let lifetime = cache.lifetime(&format!("{{\"access_token\":\"token\",\"expires_at\":{}}}", now + 20));
+        assert_eq!(lifetime, std::time::Duration::default(), "Tokens close to expiry must be refreshed");
+        let lifetime = cache.lifetime(&format!("{{\"access_token\":\"token\",\"expires_at\":{}}}", now + 90));
+        assert!(lifetime <= std::time::Duration::from_secs(60), "Invalid lifetime: {:?}", lifetime);
+        assert!(lifetime >= std::time::Duration::from_secs(59), "Invalid lifetime: {:?}", lifetime);
+        let lifetime = cache.lifetime("{}");
+        assert_eq!(lifetime, std::time::Duration::from_secs(270), "The default ttl applies without expiry");
+    }
+
+    #[test]
+    fn unauthorized_request_is_retried_once() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET);
+            then.status(401);
+        });
+        let cache = std::sync::Arc::new(TokenCache::default());
+        let sniffer = create_sniffer(&test_case, cache.clone());
+        let result = tokio_test::block_on(sniffer.get_my_orders(trading_pair()));
+        assert!(result.is_err(), "Unauthorized requests must fail after the retry");
+        assert_eq!(cache.unauthorized(), 2, "Both attempts must be rejected");
+        assert!(!cache.is_valid(), "Rejected tokens must be dropped");
+        auth_mock.assert_hits(2);
+        orders_mock.assert_hits(2);
+    }
+
+    #[test]
+    fn unauthorized_write_is_not_retried() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let delete_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::DELETE);
+            then.status(401);
+        });
+        let cache = std::sync::Arc::new(TokenCache::default());
+        let client = std::sync::Arc::new(chatex_sdk_rust::ChatexClient::new(
+            TokenCachingConnector::new(hyper::client::HttpConnector::new(), cache.clone()),
+            test_case.server.base_url().parse().expect("Invalid url"),
+            SECRET.to_owned()));
+        let trader = crate::trader::ChatexTrader::new(std::sync::Arc::new(client.exchange()))
+            .with_policy(std::sync::Arc::new(RequestPolicy {
+                token_cache: Some(cache.clone()),
+                ..RequestPolicy::default()
+            }));
+        let result = tokio_test::block_on(agnostic::market::Trader::delete_order(&trader, "42"));
+        assert!(result.is_err(), "Unauthorized writes must fail");
+        assert_eq!(cache.unauthorized(), 1, "Writes must not be sent again");
+        auth_mock.assert_hits(1);
+        delete_mock.assert_hits(1);
+    }
+}
@@ -1,12 +1,33 @@
-use crate::order::Order;
+use crate::instrument::{instrument, Span};
+use crate::order::{ChatexOrder, NormalizedOrder};
+use crate::sniffer::{my_order_ids, my_orders};
+use crate::price_band::PriceBand;
+use crate::error::Error;
+use crate::kill_switch::{CancelAllOrders, KillSwitchFlag};
+use crate::market_info::{MarketInfo, RoundingPolicy};
+use crate::policy::RequestPolicy;
+use crate::metrics::OrderEvent;
 use agnostic::market;
 use agnostic::order::OrderWithId;
 use agnostic::trade::{Trade, TradeResult};
 use agnostic::trading_pair::Target;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub(crate) const AMOUNT_EPSILON: f64 = 1e-9;
 
 pub struct ChatexTrader<TConnector> {
     client: std::sync::Arc<chatex_sdk_rust::ExchangeClient<TConnector>>,
+    price_band: Option<PriceBand>,
+    price_band_override: AtomicBool,
+    kill_switch: std::sync::Arc<KillSwitchFlag>,
+    market_info: std::sync::Arc<MarketInfo>,
+    rounding: RoundingPolicy,
+    policy: std::sync::Arc<RequestPolicy>,
+    rate_tolerance: f64,
+    book_depth: u32,
+    exclude_own_orders: bool,
+    order_pairs: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, chatex_sdk_rust::coin::CoinPair>>>,
 }
 
 impl<TConnector> ChatexTrader<TConnector>
@@ -16,7 +37,71 @@ where
     pub fn new(
         client: std::sync::Arc<chatex_sdk_rust::ExchangeClient<TConnector>>,
     ) -> ChatexTrader<TConnector> {
-        ChatexTrader { client }
+        ChatexTrader {
+            client,
+            price_band: None,
+            price_band_override: AtomicBool::new(false),
+            kill_switch: std::sync::Arc::new(KillSwitchFlag::default()),
+            market_info: std::sync::Arc::new(MarketInfo::default()),
+            rounding: RoundingPolicy::Round,
+            policy: std::sync::Arc::new(RequestPolicy::default()),
+            rate_tolerance: 0.00005,
+            book_depth: 30,
+            exclude_own_orders: false,
+            order_pairs: std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: std::sync::Arc<RequestPolicy>) -> ChatexTrader<TConnector> {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_matching(mut self, rate_tolerance: f64, book_depth: u32) -> ChatexTrader<TConnector> {
+        self.rate_tolerance = rate_tolerance;
+        self.book_depth = book_depth;
+        self
+    }
+
+    pub fn with_exclude_own_orders(mut self, exclude_own_orders: bool) -> ChatexTrader<TConnector> {
+        self.exclude_own_orders = exclude_own_orders;
+        self
+    }
+
+    pub fn with_market_info(
+        mut self,
+        market_info: std::sync::Arc<MarketInfo>,
+        rounding: RoundingPolicy,
+    ) -> ChatexTrader<TConnector> {
+        self.market_info = market_info;
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn market_info(&self) -> std::sync::Arc<MarketInfo> {
+        self.market_info.clone()
+    }
+
+    pub fn with_price_band(mut self, price_band: PriceBand) -> ChatexTrader<TConnector> {
+        self.price_band = Some(price_band);
+        self
+    }
+
+    pub fn price_band(&self) -> Option<PriceBand> {
+        self.price_band
+    }
+
+    pub fn override_price_band(&self, enabled: bool) {
+        log::warn!("Price band override: {}", enabled);
+        self.price_band_override.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_price_band_overridden(&self) -> bool {
+        self.price_band_override.load(Ordering::SeqCst)
+    }
+
+    pub fn kill_switch_flag(&self) -> std::sync::Arc<KillSwitchFlag> {
+        self.kill_switch.clone()
     }
 }
 
@@ -29,103 +114,406 @@ where
         order: agnostic::order::Order,
     ) -> market::Future<Result<Trade, String>> {
         let client = self.client.clone();
+        let price_band = if self.is_price_band_overridden() {
+            None
+        } else {
+            self.price_band
+        };
+        let kill_switch = self.kill_switch.clone();
+        let market_info = self.market_info.clone();
+        let rounding = self.rounding;
+        let policy = self.policy.clone();
+        let rate_tolerance = self.rate_tolerance;
+        let book_depth = self.book_depth;
+        let exclude_own_orders = self.exclude_own_orders;
+        let order_pairs = self.order_pairs.clone();
+        let span = Span::new("create_order").order(&order);
         let future = async move {
-            match order.trading_pair.target {
-                Target::Market => create_trade(client, order).await,
-                Target::Limit => create_order(client, order).await,
-            }
+            let converted_order = match prepare(&kill_switch, &market_info, rounding, &order) {
+                Ok(converted_order) => converted_order,
+                Err(error) => return Err(rejected(&policy, error)),
+            };
+            let trade = match order.trading_pair.target {
+                Target::Market => create_trade(
+                    client,
+                    &policy,
+                    order,
+                    converted_order,
+                    rate_tolerance,
+                    book_depth,
+                    exclude_own_orders).await?,
+                Target::Limit => {
+                    let pair = converted_order.pair.clone();
+                    let trade = create_order(client, &policy, order, converted_order, price_band, rate_tolerance).await?;
+                    if let (Trade::Limit(created), Ok(mut order_pairs)) = (&trade, order_pairs.lock()) {
+                        order_pairs.insert(created.id.clone(), pair);
+                    }
+                    trade
+                }
+            };
+            let event = match trade {
+                Trade::Market(_) => OrderEvent::Filled,
+                Trade::Limit(_) => OrderEvent::Created,
+            };
+            policy.metrics.increment_orders(event, 1);
+            Ok(trade)
         };
-        Box::pin(future)
+        instrument(span, future)
     }
 
     fn delete_order(&self, id: &str) -> agnostic::market::Future<Result<(), String>> {
         let client = self.client.clone();
+        let policy = self.policy.clone();
+        let order_pairs = self.order_pairs.clone();
         let id = id.to_owned();
+        let span = Span::new("delete_order").order_id(&id);
         let future = async move {
-            match client.delete_order_by_id(&id).await {
+            // Orders placed by other sessions have no known pair and are looked up among all pairs.
+            let pair = order_pairs.lock().ok().and_then(|order_pairs| order_pairs.get(&id).cloned());
+            match policy.write("delete_order", || client.delete_order_by_id(&id)).await {
                 Ok(order) => {
                     log::debug!("Order deleted: {:#?}", order);
-                    Ok(())
                 }
-                Err(error) => Err(format!("{}", error)),
+                Err(timeout @ Error::Timeout { .. }) => {
+                    reconcile_deleted_order(&client, &policy, &id, pair.as_ref(), timeout).await?;
+                }
+                Err(error) => return Err(error.into()),
+            }
+            if let Ok(mut order_pairs) = order_pairs.lock() {
+                order_pairs.remove(&id);
+            }
+            policy.metrics.increment_orders(OrderEvent::Cancelled, 1);
+            Ok(())
+        };
+        instrument(span, future)
+    }
+}
+
+impl<TConnector> CancelAllOrders for ChatexTrader<TConnector>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static,
+{
+    fn cancel_all_orders(&self) -> market::Future<Result<Vec<String>, String>> {
+        let client = self.client.clone();
+        let policy = self.policy.clone();
+        let span = Span::new("cancel_all_orders");
+        let future = async move {
+            let orders = my_orders(&client, &policy, "get_my_orders", None).await?;
+            let mut cancelled = Vec::with_capacity(orders.len());
+            let mut errors = Vec::new();
+            for order in orders {
+                let id = order.id.to_string();
+                match policy.write("delete_order", || client.delete_order_by_id(&id)).await {
+                    Ok(order) => {
+                        log::debug!("Order deleted: {:#?}", order);
+                        cancelled.push(id);
+                    }
+                    Err(error) => errors.push(format!("{}: {}", id, error)),
+                }
+            }
+            policy.metrics.increment_orders(OrderEvent::Cancelled, cancelled.len() as u64);
+            if errors.is_empty() {
+                Ok(cancelled)
+            } else {
+                Err(format!(
+                    "Failed to cancel orders: {}. Cancelled: {:?}",
+                    errors.join(", "),
+                    cancelled))
             }
         };
-        Box::pin(future)
+        instrument(span, future)
+    }
+}
+
+fn prepare(
+    kill_switch: &KillSwitchFlag,
+    market_info: &MarketInfo,
+    rounding: RoundingPolicy,
+    order: &agnostic::order::Order,
+) -> Result<ChatexOrder, Error> {
+    if kill_switch.is_triggered() {
+        return Err(Error::KillSwitchTriggered);
+    }
+    if !market_info.is_enabled(&order.trading_pair) {
+        return Err(Error::PairDisabled {
+            pair: crate::converter::pair_key(&order.trading_pair.coins),
+        });
+    }
+    let converted_order = NormalizedOrder::from(order.clone()).to_chatex();
+    if !market_info.is_listed(&converted_order.pair) {
+        return Err(Error::PairDisabled {
+            pair: String::from(converted_order.pair),
+        });
+    }
+    market_info
+        .rules(&converted_order.pair)
+        .normalize(converted_order, &order.trading_pair.target, rounding)
+}
+
+fn rejected(policy: &RequestPolicy, error: Error) -> String {
+    policy.metrics.increment_error(error.kind());
+    error.into()
+}
+
+fn unreconciled(policy: &RequestPolicy, operation: &str, error: Error) -> String {
+    rejected(policy, Error::Unreconciled {
+        operation: operation.to_owned(),
+        message: error.to_string(),
+    })
+}
+
+fn parse_number(value: &str) -> f64 {
+    f64::from_str(value).unwrap_or(f64::NAN)
+}
+
+async fn reconcile_created_order<TConnector>(
+    client: &chatex_sdk_rust::ExchangeClient<TConnector>,
+    policy: &RequestPolicy,
+    order: &ChatexOrder,
+    known_ids: &std::collections::HashSet<u32>,
+    rate_tolerance: f64,
+    timeout: Error,
+) -> Result<chatex_sdk_rust::models::Order, String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static
+{
+    let orders = my_orders(client, policy, "reconcile_order", Some(&order.pair))
+        .await
+        .map_err(|error| unreconciled(policy, "create_order", error))?;
+    let placed = orders
+        .into_iter()
+        .filter(|placed| !known_ids.contains(&placed.id))
+        .filter(|placed| (parse_number(&placed.rate) - order.rate).abs() < rate_tolerance)
+        .filter(|placed| parse_number(&placed.amount) <= order.amount + AMOUNT_EPSILON)
+        .max_by_key(|placed| placed.id);
+    match placed {
+        Some(placed) => {
+            log::warn!("create_order timed out, but the order {} was placed", placed.id);
+            Ok(placed)
+        }
+        None => {
+            log::warn!("create_order timed out and the order was not placed");
+            Err(timeout.into())
+        }
+    }
+}
+
+async fn reconcile_deleted_order<TConnector>(
+    client: &chatex_sdk_rust::ExchangeClient<TConnector>,
+    policy: &RequestPolicy,
+    id: &str,
+    pair: Option<&chatex_sdk_rust::coin::CoinPair>,
+    timeout: Error,
+) -> Result<(), String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static
+{
+    let orders = my_orders(client, policy, "reconcile_order", pair)
+        .await
+        .map_err(|error| unreconciled(policy, "delete_order", error))?;
+    if orders.iter().any(|order| order.id.to_string() == id) {
+        log::warn!("delete_order timed out and the order {} is still open", id);
+        Err(timeout.into())
+    } else {
+        log::warn!("delete_order timed out, but the order {} is gone", id);
+        Ok(())
+    }
+}
+
+// Other takers can fill the same order, so only an untouched order proves that
+// the trade was not executed. Any other change is left to the caller.
+async fn reconcile_trade<TConnector>(
+    client: &chatex_sdk_rust::ExchangeClient<TConnector>,
+    policy: &RequestPolicy,
+    pair: &chatex_sdk_rust::coin::CoinPair,
+    order: &chatex_sdk_rust::models::Order,
+    amount: f64,
+    book_depth: u32,
+    timeout: Error,
+) -> String
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static
+{
+    let orders = match policy
+        .read("reconcile_trade", || client.get_all_orders(pair.clone(), None, Some(book_depth)))
+        .await
+    {
+        Ok(orders) => orders,
+        Err(error) => return unreconciled(policy, "create_trade", error),
+    };
+    let before = parse_number(&order.amount);
+    match orders.iter().find(|remaining| remaining.id == order.id) {
+        Some(remaining) if (parse_number(&remaining.amount) - before).abs() <= AMOUNT_EPSILON => {
+            log::warn!("create_trade timed out and the trade against {} was not executed", order.id);
+            timeout.into()
+        }
+        remaining => {
+            let after = remaining.map_or(0.0, |remaining| parse_number(&remaining.amount));
+            rejected(policy, Error::Unreconciled {
+                operation: "create_trade".to_owned(),
+                message: format!(
+                    "The order {} changed from {} to {}. The trade of {} can not be attributed",
+                    order.id,
+                    before,
+                    after,
+                    amount),
+            })
+        }
     }
 }
 
 async fn create_order<TConnector>(
     client: std::sync::Arc<chatex_sdk_rust::ExchangeClient<TConnector>>,
+    policy: &RequestPolicy,
     order: agnostic::order::Order,
+    converted_order: ChatexOrder,
+    price_band: Option<PriceBand>,
+    rate_tolerance: f64,
 ) -> Result<Trade, String> 
 where
     TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static
 {
-    let trading_pair = order.trading_pair.clone();
-    let converted_order: Order = order.into();
-    let created_order = match client
-        .create_order(
-            converted_order.pair,
-            converted_order.amount,
-            converted_order.rate,
+    let trading_pair = order.trading_pair;
+    if let Some(price_band) = price_band {
+        check_price_band(&client, policy, &converted_order, &price_band).await?;
+    }
+    let known_ids = match policy.timeout_for("create_order") {
+        Some(_) => my_order_ids(&client, policy, &converted_order.pair).await?,
+        None => std::collections::HashSet::new(),
+    };
+    let created_order = match policy
+        .write(
+            "create_order",
+            || client.create_order(
+                converted_order.pair.clone(),
+                converted_order.amount,
+                converted_order.rate,
+            ),
         )
         .await
     {
         Ok(order) => order,
-        Err(error) => return Err(format!("{}", error)),
+        Err(timeout @ Error::Timeout { .. }) => {
+            reconcile_created_order(&client, policy, &converted_order, &known_ids, rate_tolerance, timeout).await?
+        }
+        Err(error) => return Err(error.into()),
+    };
+    let created_order = NormalizedOrder::from_raw(&trading_pair, &created_order);
+    let id = match created_order.id {
+        Some(id) => id.to_string(),
+        None => return Err("Invalid response from server. Order id is None.".to_owned()),
     };
-    let created_order = Order::from_raw(&trading_pair, &created_order);
+    crate::instrument::record_order_id(&id);
     Ok(Trade::Limit(OrderWithId {
-        id: match created_order.id {
-            Some(id) => id.to_string(),
-            None => return Err("Invalid response from server. Order id is None.".to_owned()),
-        },
+        id,
         trading_pair,
-        price: created_order.rate,
+        price: created_order.price,
         amount: created_order.amount,
     }))
 }
 
+async fn check_price_band<TConnector>(
+    client: &chatex_sdk_rust::ExchangeClient<TConnector>,
+    policy: &RequestPolicy,
+    order: &ChatexOrder,
+    price_band: &PriceBand,
+) -> Result<(), String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static
+{
+    match best_rate(client, policy, order.pair.clone()).await? {
+        Some(best_rate) => price_band
+            .check(order.rate, best_rate)
+            .map_err(|error| rejected(policy, error)),
+        None => {
+            log::warn!("Price band check skipped. The book is empty: {}", String::from(order.pair.clone()));
+            Ok(())
+        }
+    }
+}
+
+async fn best_rate<TConnector>(
+    client: &chatex_sdk_rust::ExchangeClient<TConnector>,
+    policy: &RequestPolicy,
+    pair: chatex_sdk_rust::coin::CoinPair,
+) -> Result<Option<f64>, String>
+where
+    TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static
+{
+    let orders = policy
+        .read("best_rate", || client.get_all_orders(pair.clone(), None, Some(1)))
+        .await?;
+    if let Some(order) = orders.first() {
+        return f64::from_str(&order.rate)
+            .map(Some)
+            .map_err(|error| format!("Invalid order rate: {}", error));
+    }
+    let orders = policy
+        .read("best_rate", || client.get_all_orders(pair.reversed(), None, Some(1)))
+        .await?;
+    match orders.first() {
+        Some(order) => f64::from_str(&order.rate)
+            .map(|rate| Some(1.0 / rate))
+            .map_err(|error| format!("Invalid order rate: {}", error)),
+        None => Ok(None),
+    }
+}
+
 async fn create_trade<TConnector>(
     client: std::sync::Arc<chatex_sdk_rust::ExchangeClient<TConnector>>,
-    new_order: agnostic::order::Order
+    policy: &RequestPolicy,
+    new_order: agnostic::order::Order,
+    converted_order: ChatexOrder,
+    rate_tolerance: f64,
+    book_depth: u32,
+    exclude_own_orders: bool,
 ) -> Result<Trade, String> 
 where
     TConnector: hyper::client::connect::Connect + Send + Sync + Clone + 'static
 {
     let trading_pair = new_order.trading_pair.clone();
-    let converted_order: Order = new_order.clone().into();
-    let orders = match client
-        .get_all_orders(converted_order.pair.clone(), None, Some(30))
-        .await
-    {
-        Ok(orders) => orders,
-        Err(error) => return Err(format!("{:#?}", error)),
+    let my_ids = if exclude_own_orders {
+        my_order_ids(&client, policy, &converted_order.pair).await?
+    } else {
+        std::collections::HashSet::new()
     };
-    if let Some(order) = orders.iter().find(|order| {
+    let limit = book_depth.saturating_add(my_ids.len() as u32);
+    let orders = policy
+        .read("order_book", || client.get_all_orders(converted_order.pair.clone(), None, Some(limit)))
+        .await?;
+    if let Some(order) = orders.iter().filter(|order| !my_ids.contains(&order.id)).find(|order| {
         let order_rate = f64::from_str(&order.rate).unwrap();
         let rate = converted_order.rate;
-        (order_rate - rate).abs() < 0.00005
+        (order_rate - rate).abs() < rate_tolerance
     }) {
         let trade = chatex_sdk_rust::models::CreateTradeRequest {
             amount: converted_order.amount.to_string(),
             rate: "133713371337.1337".to_owned(),
         };
-        log::info!("Create trade request: Id: {} Trade: {:#?}", order.id, trade);
-        match client
-            .create_trade_for_order(&order.id.to_string(), &trade)
+        crate::instrument::record_order_id(&order.id.to_string());
+        crate::instrument::info(format_args!("Create trade request: Id: {} Trade: {:?}", order.id, trade));
+        match policy
+            .write("create_trade", || client.create_trade_for_order(&order.id.to_string(), &trade))
             .await
         {
             Ok(trade) => {
-                let trade = Order::from_trade(&trading_pair, trade);
+                let trade = NormalizedOrder::from_trade(&trading_pair, trade);
                 Ok(Trade::Market(TradeResult {
                     id: trade.id.expect("Invalid trade").to_string(),
                     trading_pair,
                     amount: new_order.amount,
-                    price: trade.rate,
+                    price: trade.price,
                 }))
             },
-            Err(error) => Err(error.to_string()),
+            Err(timeout @ Error::Timeout { .. }) => Err(reconcile_trade(
+                &client,
+                policy,
+                &converted_order.pair,
+                order,
+                converted_order.amount,
+                book_depth,
+                timeout).await),
+            Err(error) => Err(error.into()),
         }
     } else {
         Err(format!("Failed to find the order: {:#?}", new_order))
@@ -206,4 +594,256 @@ mod test {
         orders_mock.assert();
         trade_mock.assert()
     }
+
+    #[test]
+    fn delete_order_timeout_is_reconciled() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let delete_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::DELETE);
+            then.status(200)
+                .delay(std::time::Duration::from_millis(500));
+        });
+        let my_orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET);
+            let body: Vec<chatex_sdk_rust::models::Order> = Vec::new();
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_string(&body).expect(SERDE_ERROR));
+        });
+        let policy = RequestPolicy {
+            operation_timeouts: vec![("delete_order".to_owned(), std::time::Duration::from_millis(50))],
+            ..RequestPolicy::default()
+        };
+        let trader = ChatexTrader::new(std::sync::Arc::new(test_case.client.exchange()))
+            .with_policy(std::sync::Arc::new(policy));
+        let result = tokio_test::block_on(trader.delete_order("42"));
+        assert!(result.is_ok(), format!("The deleted order must be reconciled: {:#?}", result.err()));
+        let expected = Error::Timeout {
+            operation: "delete_order".to_owned(),
+            timeout_ms: 50,
+        };
+        let policy = RequestPolicy {
+            timeout: Some(std::time::Duration::from_millis(50)),
+            ..RequestPolicy::default()
+        };
+        let client = test_case.client.exchange();
+        let result = tokio_test::block_on(policy.write("delete_order", || client.delete_order_by_id("42")));
+        assert_eq!(result.err(), Some(expected), "Timeout must be a distinct error");
+        auth_mock.assert_hits(3);
+        delete_mock.assert_hits(2);
+        my_orders_mock.assert();
+    }
+
+    #[test]
+    fn create_order_out_of_price_band() {
+        let test_case = TestCase::default();
+        let auth_mock = test_case.mock_access_token();
+        let orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET);
+            let order = chatex_sdk_rust::models::typed::Order::new(
+                chatex_sdk_rust::coin::CoinPair::new(
+                    chatex_sdk_rust::coin::Coin::TON,
+                    chatex_sdk_rust::coin::Coin::USDT),
+                2.0,
+                4.0);
+            let body: chatex_sdk_rust::models::Order = order.into();
+            let body = serde_json::to_string(&vec![body]).expect(SERDE_ERROR);
+            then
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(body);
+        });
+        let create_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::POST)
+                .path("/exchange/orders");
+            then.status(201);
+        });
+        let metrics = std::sync::Arc::new(crate::metrics::PrometheusRecorder::default());
+        let policy = RequestPolicy {
+            metrics: metrics.clone(),
+            ..RequestPolicy::default()
+        };
+        let trader = ChatexTrader::new(std::sync::Arc::new(test_case.client.exchange()))
+            .with_price_band(PriceBand::new(10.0))
+            .with_policy(std::sync::Arc::new(policy));
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                target: Target::Limit,
+                side: Side::Sell,
+            },
+            amount: 2.0,
+            price: 0.5,
+        };
+        let trade_result = tokio_test::block_on(trader.create_order(order));
+        assert!(trade_result.is_err(), "Inverted price must be rejected");
+        assert_eq!(metrics.errors("price_out_of_band"), 1, "The rejection must be counted");
+        assert_eq!(metrics.requests("best_rate"), 1, "The book request must be timed");
+        assert_eq!(metrics.orders(crate::metrics::OrderEvent::Created), 0);
+        auth_mock.assert();
+        orders_mock.assert();
+        create_mock.assert_hits(0);
+    }
+
+    #[test]
+    fn prepare_reversed_order() {
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::USDT,
+            chatex_sdk_rust::coin::Coin::TON);
+        let market_info = MarketInfo::default()
+            .with_rules(&pair.reversed(), crate::market_info::MarketRules {
+                min_amount: 5.0,
+                ..Default::default()
+            })
+            .with_rules(&pair, crate::market_info::MarketRules {
+                min_amount: 1.0,
+                min_notional: 0.5,
+                amount_step: 0.01,
+                price_step: 0.0001,
+            });
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                side: Side::Buy,
+                target: Target::Limit,
+            },
+            price: 3.0,
+            amount: 1.0,
+        };
+        let kill_switch = KillSwitchFlag::default();
+        let prepared = prepare(&kill_switch, &market_info, RoundingPolicy::Round, &order)
+            .expect("The USDT/TON rules must be applied");
+        assert_eq!(String::from(prepared.pair.clone()), String::from(pair.clone()), "Invalid pair");
+        assert_eq!(prepared.rate, 0.3334, "The rate must be rounded on the USDT/TON step");
+        assert_eq!(prepared.amount, 3.0, "The amount must be in USDT");
+        let rejected = prepare(&kill_switch, &market_info, RoundingPolicy::Reject, &order);
+        assert!(rejected.is_err(), "The rate is not on the USDT/TON step");
+        let order = agnostic::order::Order { amount: 0.3, ..order };
+        let rejected = prepare(&kill_switch, &market_info, RoundingPolicy::Round, &order);
+        assert!(
+            matches!(rejected, Err(Error::AmountBelowMinimum { .. })),
+            "The minimum must be checked in USDT: {:?}", rejected.err());
+    }
+
+    #[test]
+    fn create_order_timeout_ignores_existing_orders() {
+        let test_case = TestCase::default();
+        let _auth_mock = test_case.mock_access_token();
+        let create_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::POST)
+                .path("/exchange/orders");
+            then.status(201)
+                .delay(std::time::Duration::from_millis(500));
+        });
+        let my_orders_mock = test_case.server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path("/exchange/my-orders");
+            let order = chatex_sdk_rust::models::typed::Order::new(
+                chatex_sdk_rust::coin::CoinPair::new(
+                    chatex_sdk_rust::coin::Coin::TON,
+                    chatex_sdk_rust::coin::Coin::USDT),
+                2.0,
+                1.0);
+            let body: Vec<chatex_sdk_rust::models::Order> = vec![order.into()];
+            then.status(200)
+                .header("Content-Type", "application/json")
+                .body(serde_json::to_string(&body).expect(SERDE_ERROR));
+        });
+        let policy = RequestPolicy {
+            operation_timeouts: vec![("create_order".to_owned(), std::time::Duration::from_millis(50))],
+            ..RequestPolicy::default()
+        };
+        let trader = ChatexTrader::new(std::sync::Arc::new(test_case.client.exchange()))
+            .with_policy(std::sync::Arc::new(policy));
+        let order = agnostic::order::Order {
+            trading_pair: TradingPair {
+                coins: Coins::TonUsdt,
+                side: Side::Sell,
+                target: Target::Limit,
+            },
+            price: 2.0,
+            amount: 1.0,
+        };
+        let result = tokio_test::block_on(trader.create_order(order));
+        assert!(result.is_err(), "A pre-existing order must not be claimed");
+        create_mock.assert();
+        my_orders_mock.assert_hits(2);
+    }
+
+    #[test]
+    fn cancel_all_orders_on_every_page() {
+        let chatex = crate::fake_server::FakeChatex::start(crate::test::SECRET);
+        let mut ids = add_my_orders(&chatex, 1.0);
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        ids.insert(chatex.add_my_order(pair, 2.0, 1.0));
+        let trader = create_trader(chatex.client());
+        let cancelled = tokio_test::block_on(trader.cancel_all_orders()).expect("Failed to cancel orders");
+        assert_eq!(cancelled.len(), ids.len(), "Orders past the first page must be cancelled");
+        assert!(chatex.orders().is_empty(), "No order must stay live");
+    }
+
+    fn add_my_orders(chatex: &crate::fake_server::FakeChatex, rate: f64) -> std::collections::HashSet<u32> {
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        (0..crate::sniffer::MY_ORDERS_PAGE)
+            .map(|_| chatex.add_my_order(pair.clone(), rate, 1.0))
+            .collect()
+    }
+
+    fn timeout(operation: &str) -> Error {
+        Error::Timeout {
+            operation: operation.to_owned(),
+            timeout_ms: 50,
+        }
+    }
+
+    #[test]
+    fn reconcile_created_order_on_the_second_page() {
+        let chatex = crate::fake_server::FakeChatex::start(crate::test::SECRET);
+        let known_ids = add_my_orders(&chatex, 1.0);
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        let id = chatex.add_my_order(pair.clone(), 2.0, 1.0);
+        let exchange = chatex.client().exchange();
+        let order = ChatexOrder {
+            id: None,
+            pair,
+            rate: 2.0,
+            amount: 1.0,
+        };
+        let placed = tokio_test::block_on(reconcile_created_order(
+            &exchange,
+            &RequestPolicy::default(),
+            &order,
+            &known_ids,
+            0.00005,
+            timeout("create_order")));
+        assert_eq!(placed.map(|placed| placed.id), Ok(id), "Orders past the first page must be found");
+    }
+
+    #[test]
+    fn reconcile_deleted_order_on_the_second_page() {
+        let chatex = crate::fake_server::FakeChatex::start(crate::test::SECRET);
+        add_my_orders(&chatex, 1.0);
+        let pair = chatex_sdk_rust::coin::CoinPair::new(
+            chatex_sdk_rust::coin::Coin::TON,
+            chatex_sdk_rust::coin::Coin::USDT);
+        let id = chatex.add_my_order(pair.clone(), 2.0, 1.0).to_string();
+        let exchange = chatex.client().exchange();
+        let policy = RequestPolicy::default();
+        let result = tokio_test::block_on(
+            reconcile_deleted_order(&exchange, &policy, &id, Some(&pair), timeout("delete_order")));
+        assert!(result.is_err(), "An open order past the first page must not be reported as deleted");
+        let result = tokio_test::block_on(
+            reconcile_deleted_order(&exchange, &policy, &id, None, timeout("delete_order")));
+        assert!(result.is_err(), "Orders of unknown pairs must be looked up among all pairs");
+        let result = tokio_test::block_on(
+            reconcile_deleted_order(&exchange, &policy, "999999", Some(&pair), timeout("delete_order")));
+        assert!(result.is_ok(), "Missing orders are deleted");
+    }
 }
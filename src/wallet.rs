@@ -0,0 +1,106 @@
+use crate::converter;
+use agnostic::currency::Currency;
+use agnostic::trading_pair::{Coin, Side, TradingPair};
+
+const AMOUNT_EPSILON: f64 = 0.00005;
+
+#[derive(Clone, Debug, Default)]
+pub struct Wallet {
+    balances: Vec<Currency>,
+}
+
+impl Wallet {
+    pub fn new(balances: Vec<Currency>) -> Wallet {
+        Wallet { balances }
+    }
+
+    pub fn balances(&self) -> Vec<Currency> {
+        self.balances.clone()
+    }
+
+    pub fn balance(&self, coin: &Coin) -> Currency {
+        self.balances
+            .iter()
+            .find(|currency| currency.coin == *coin)
+            .cloned()
+            .unwrap_or(Currency {
+                coin: coin.clone(),
+                amount: 0.0,
+                held: 0.0,
+            })
+    }
+
+    fn balance_mut(&mut self, coin: &Coin) -> &mut Currency {
+        match self.balances.iter().position(|currency| currency.coin == *coin) {
+            Some(index) => &mut self.balances[index],
+            None => {
+                self.balances.push(Currency {
+                    coin: coin.clone(),
+                    amount: 0.0,
+                    held: 0.0,
+                });
+                self.balances.last_mut().expect("Balance was just pushed")
+            }
+        }
+    }
+
+    pub fn ensure(&self, coin: &Coin, amount: f64) -> Result<(), String> {
+        let available = self.balance(coin).amount;
+        if available + AMOUNT_EPSILON < amount {
+            Err(format!(
+                "Insufficient balance of {:?}: {} < {}",
+                coin,
+                available,
+                amount))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn hold(&mut self, coin: &Coin, amount: f64) -> Result<(), String> {
+        self.ensure(coin, amount)?;
+        let balance = self.balance_mut(coin);
+        balance.amount -= amount;
+        balance.held += amount;
+        Ok(())
+    }
+
+    pub fn release(&mut self, coin: &Coin, amount: f64) {
+        let balance = self.balance_mut(coin);
+        balance.held -= amount;
+        balance.amount += amount;
+    }
+
+    pub fn required(trading_pair: &TradingPair, price: f64, amount: f64) -> (Coin, f64) {
+        let (base, quote) = converter::split_coins(&trading_pair.coins);
+        match trading_pair.side {
+            Side::Buy => (quote, price * amount),
+            Side::Sell => (base, amount),
+        }
+    }
+
+    pub fn settle(
+        &mut self,
+        trading_pair: &TradingPair,
+        price: f64,
+        amount: f64,
+        fee_rate: f64,
+    ) -> (Coin, f64) {
+        let (base, quote) = converter::split_coins(&trading_pair.coins);
+        let volume = price * amount;
+        match trading_pair.side {
+            Side::Buy => {
+                let fee = amount * fee_rate;
+                self.balance_mut(&quote).amount -= volume;
+                self.balance_mut(&base).amount += amount - fee;
+                (base, fee)
+            }
+            Side::Sell => {
+                let fee = volume * fee_rate;
+                self.balance_mut(&base).amount -= amount;
+                self.balance_mut(&quote).amount += volume - fee;
+                (quote, fee)
+            }
+        }
+    }
+}